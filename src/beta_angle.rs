@@ -0,0 +1,154 @@
+use crate::constants::{EARTH_RADIUS, MINUTES_PER_DAY, PI, TWO_PI};
+use crate::propagator::PropagatorError;
+use crate::sun::sun_position_teme;
+use crate::vector::{dot, norm, Vec3};
+use crate::SatRec;
+use serde::Serialize;
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Beta angle and orbit lighting at one date. Angles are in radians.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BetaAngleSample {
+    /// Julian date.
+    pub jd: f64,
+    /// Angle between the Sun direction and the orbit plane, positive when the
+    /// Sun is on the side of the orbit normal.
+    pub beta: f64,
+    /// Fraction of each orbit spent in the Earth's shadow.
+    pub eclipse_fraction: f64,
+    /// Time spent in the Earth's shadow per orbit, in minutes.
+    pub eclipse_duration: f64,
+}
+
+/// Solar beta angle of `satrec` at Julian date `jd`, radians.
+///
+/// The orbit normal is built from the mean inclination and the right
+/// ascension of the ascending node advanced by its secular drift `nodedot`.
+#[wasm_bindgen(js_name = "betaAngle")]
+pub fn beta_angle(satrec: &SatRec, jd: f64) -> f64 {
+    let tsince = (jd - satrec.jdsatepoch) * MINUTES_PER_DAY;
+    let node = satrec.nodeo + satrec.nodedot * tsince;
+    let normal = [
+        satrec.inclo.sin() * node.sin(),
+        -satrec.inclo.sin() * node.cos(),
+        satrec.inclo.cos(),
+    ];
+    let sun: Vec3 = (&sun_position_teme(jd)).into();
+    (dot(&normal, &sun) / norm(&sun)).asin()
+}
+
+/// Fraction of a circular orbit of altitude `altitude` (km) spent in a
+/// cylindrical Earth shadow for a beta angle `beta` (radians).
+#[wasm_bindgen(js_name = "eclipseFraction")]
+pub fn eclipse_fraction(beta: f64, altitude: f64) -> f64 {
+    let r = EARTH_RADIUS + altitude;
+    let beta_star = (EARTH_RADIUS / r).asin();
+    if beta.abs() >= beta_star {
+        return 0.0;
+    }
+    let x = (altitude * altitude + 2.0 * EARTH_RADIUS * altitude).sqrt() / (r * beta.cos());
+    x.clamp(-1.0, 1.0).acos() / PI
+}
+
+/// Beta angle and eclipse duration per orbit of `satrec` sampled every
+/// `step` days from `start` to `end` (Julian dates).
+///
+/// The eclipse figures treat the orbit as circular at the mean semi-major
+/// axis, which suits the near-circular orbits where beta matters most.
+/// Returns [`PropagatorError::InvalidWindow`] unless `step` is positive and
+/// `end` is not before `start`.
+pub fn beta_angle_history(
+    satrec: &SatRec,
+    start: f64,
+    end: f64,
+    step: f64,
+) -> Result<Vec<BetaAngleSample>, PropagatorError> {
+    if step <= 0.0 || end < start {
+        return Err(PropagatorError::InvalidWindow);
+    }
+    let period = TWO_PI / satrec.no;
+    let altitude = (satrec.a - 1.0) * EARTH_RADIUS;
+    let n = ((end - start) / step).floor().max(0.0) as usize;
+
+    Ok((0..=n)
+        .map(|i| {
+            let jd = start + step * (i as f64);
+            let beta = beta_angle(satrec, jd);
+            let fraction = eclipse_fraction(beta, altitude);
+            BetaAngleSample {
+                jd,
+                beta,
+                eclipse_fraction: fraction,
+                eclipse_duration: fraction * period,
+            }
+        })
+        .collect())
+}
+
+#[wasm_bindgen(js_name = "betaAngleHistory")]
+pub fn js_beta_angle_history(
+    satrec: &SatRec,
+    start: f64,
+    end: f64,
+    step: f64,
+) -> Result<JsValue, JsValue> {
+    match beta_angle_history(satrec, start, end, step) {
+        Ok(samples) => Ok(to_value(&samples).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{DEG2RAD, RAD2DEG};
+    use crate::ext::jday;
+    use crate::io::twoline2satrec;
+
+    #[test]
+    fn test_beta_angle_equatorial_orbit() {
+        let mut satrec = SatRec::new();
+        satrec.inclo = 0.0;
+        satrec.jdsatepoch = jday(2024.0, 3.0, 20.0, 3.0, 6.0, 0.0, 0.0);
+
+        // the Sun crosses the equator at the equinox and is 23.44° north of
+        // it at the June solstice
+        let equinox = beta_angle(&satrec, satrec.jdsatepoch);
+        let solstice = beta_angle(&satrec, jday(2024.0, 6.0, 20.0, 20.0, 51.0, 0.0, 0.0));
+        assert!((equinox * RAD2DEG).abs() < 0.01);
+        assert!((solstice * RAD2DEG - 23.44).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_eclipse_fraction() {
+        // a 400 km orbit spends about 39% of each orbit in shadow at beta = 0
+        assert!((eclipse_fraction(0.0, 400.0) - 0.390).abs() < 0.001);
+        assert!(eclipse_fraction(60.0 * DEG2RAD, 400.0) < eclipse_fraction(0.0, 400.0));
+        assert_eq!(eclipse_fraction(75.0 * DEG2RAD, 400.0), 0.0);
+    }
+
+    #[test]
+    fn test_beta_angle_history() {
+        let satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let start = satrec.jdsatepoch;
+        let history = beta_angle_history(&satrec, start, start + 10.0, 1.0).unwrap();
+        assert_eq!(history.len(), 11);
+        assert_eq!(history[3].beta, beta_angle(&satrec, start + 3.0));
+
+        for step in [0.0, -1.0] {
+            assert_eq!(
+                beta_angle_history(&satrec, start, start + 10.0, step).unwrap_err(),
+                PropagatorError::InvalidWindow
+            );
+        }
+        assert_eq!(
+            beta_angle_history(&satrec, start, start - 10.0, 1.0).unwrap_err(),
+            PropagatorError::InvalidWindow
+        );
+    }
+}
@@ -0,0 +1,498 @@
+//! Probability of collision in the encounter plane.
+//!
+//! The short-encounter assumption holds: the relative motion is taken as
+//! rectilinear around the time of closest approach and the position
+//! uncertainty as constant, so the problem reduces to integrating a 2D
+//! Gaussian over the combined hard-body disc in the plane normal to the
+//! relative velocity.
+//!
+//! Test set used below, all with a 20 m hard-body radius:
+//! - zero miss distance with an isotropic covariance, where every method must
+//!   give `1 - exp(-R² / 2σ²)`;
+//! - a 100 m miss with an isotropic covariance, where Chan's series is exact
+//!   and the integrals must match it;
+//! - an anisotropic covariance (σ = 50 m and 300 m) with an oblique miss,
+//!   where Foster and Alfano must agree and Chan stays within 10%;
+//! - a miss of many standard deviations, where the probability vanishes;
+//! - Alfano's (2005) maximum probability for an isotropic covariance,
+//!   `R² / (e d²)` at `σ = d / √2`, and the small-disc limit
+//!   `R² / (2 σx σz) exp(-(x² / σx² + z² / σz²) / 2)` used by Chan (2008),
+//!   both for discs much smaller than the covariance.
+
+use crate::constants::{PI, TWO_PI};
+use crate::vector::{cross, dot, mat_vec, norm, Mat3, Vec3};
+use crate::EciVec3;
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Fewest integration intervals used by the numerical methods.
+const MIN_INTERVALS: usize = 64;
+
+/// Most integration intervals used by the numerical methods.
+const MAX_INTERVALS: usize = 2000;
+
+/// How a 2D probability of collision is evaluated.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PcMethod {
+    /// Direct numerical integration of the Gaussian over the hard-body disc
+    /// (Foster & Estes, 1992).
+    Foster,
+    /// Analytic series built on an equal-area isotropic approximation of the
+    /// covariance (Chan, 2008). Exact for isotropic covariances.
+    Chan,
+    /// One-dimensional integral of error functions across the hard-body disc
+    /// (Alfano, 2005).
+    Alfano,
+}
+
+/// Why a probability of collision could not be evaluated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CollisionError {
+    /// The objects do not move relative to each other, so there is no
+    /// encounter plane.
+    ZeroRelativeVelocity,
+    /// The covariance in the encounter plane is not positive definite: the
+    /// uncertainty vanishes along some direction.
+    InvalidCovariance,
+}
+
+// Complementary error function, accurate to near machine precision: a
+// series of positive terms for small arguments and a continued fraction in
+// the tail.
+fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        return 2.0 - erfc(-x);
+    }
+    if x < 3.0 {
+        // erf(x) = 2/sqrt(pi) exp(-x²) sum 2^n x^(2n+1) / (1·3·…·(2n+1))
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 0.0;
+        while term > 1e-17 * sum {
+            n += 1.0;
+            term *= 2.0 * x * x / (2.0 * n + 1.0);
+            sum += term;
+        }
+        return 1.0 - 2.0 / PI.sqrt() * (-x * x).exp() * sum;
+    }
+    // erfc(x) = exp(-x²)/sqrt(pi) · 1/(x + (1/2)/(x + 1/(x + (3/2)/(x + …))))
+    // evaluated with the modified Lentz algorithm
+    let tiny = 1e-300;
+    let mut f = x;
+    let mut c = x;
+    let mut d = 0.0;
+    for k in 1..200 {
+        let a = 0.5 * k as f64;
+        d = x + a * d;
+        d = if d == 0.0 { tiny } else { 1.0 / d };
+        c = x + a / c;
+        if c == 0.0 {
+            c = tiny;
+        }
+        let delta = c * d;
+        f *= delta;
+        if (delta - 1.0).abs() < 1e-16 {
+            break;
+        }
+    }
+    (-x * x).exp() / (PI.sqrt() * f)
+}
+
+// Half the integral of the error function density between `lo` and `hi`,
+// i.e. (erf(hi) - erf(lo)) / 2, without cancellation in the tails.
+fn erf_interval(lo: f64, hi: f64) -> f64 {
+    if lo > 0.0 {
+        0.5 * (erfc(lo) - erfc(hi))
+    } else if hi < 0.0 {
+        0.5 * (erfc(-hi) - erfc(-lo))
+    } else {
+        0.5 * (2.0 - erfc(hi) - erfc(-lo))
+    }
+}
+
+// Interval count for a numerical integral: even, and fine enough to resolve
+// the narrowest axis of the Gaussian across the disc.
+fn intervals(radius: f64, sigma: f64) -> usize {
+    let n = (8.0 * radius / sigma).ceil() as usize;
+    n.clamp(MIN_INTERVALS, MAX_INTERVALS).div_ceil(2) * 2
+}
+
+/// Miss vector and position covariance projected on the encounter plane,
+/// which is normal to the relative velocity.
+///
+/// The first axis points along the miss vector, the second completes a
+/// right-handed frame with the relative velocity. `covariance` is the
+/// combined covariance of both objects (km²) in the same frame as the
+/// relative state.
+pub fn encounter_plane(
+    relative_position: &EciVec3,
+    relative_velocity: &EciVec3,
+    covariance: &Mat3,
+) -> Result<([f64; 2], [[f64; 2]; 2]), CollisionError> {
+    let r: Vec3 = relative_position.into();
+    let v: Vec3 = relative_velocity.into();
+    let speed = norm(&v);
+    if speed == 0.0 {
+        return Err(CollisionError::ZeroRelativeVelocity);
+    }
+    let along = [v[0] / speed, v[1] / speed, v[2] / speed];
+
+    // miss component normal to the relative velocity
+    let radial = dot(&r, &along);
+    let miss = [
+        r[0] - radial * along[0],
+        r[1] - radial * along[1],
+        r[2] - radial * along[2],
+    ];
+    let miss_distance = norm(&miss);
+    let x: Vec3 = if miss_distance > 0.0 {
+        [
+            miss[0] / miss_distance,
+            miss[1] / miss_distance,
+            miss[2] / miss_distance,
+        ]
+    } else {
+        // any direction normal to the velocity will do
+        let helper = if along[0].abs() < 0.9 {
+            [1.0, 0.0, 0.0]
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+        let normal = cross(&along, &helper);
+        let n = norm(&normal);
+        [normal[0] / n, normal[1] / n, normal[2] / n]
+    };
+    let z = cross(&along, &x);
+
+    let cx = mat_vec(covariance, &x);
+    let cz = mat_vec(covariance, &z);
+    Ok((
+        [miss_distance, 0.0],
+        [[dot(&x, &cx), dot(&x, &cz)], [dot(&z, &cx), dot(&z, &cz)]],
+    ))
+}
+
+/// Probability that two objects collide given the miss vector `miss` and the
+/// combined position covariance `covariance` in the encounter plane (km and
+/// km²), for a combined hard-body radius `radius` (km).
+///
+/// The covariance must be positive definite; a zero standard deviation
+/// along either principal axis gives [`CollisionError::InvalidCovariance`].
+pub fn collision_probability_2d(
+    miss: [f64; 2],
+    covariance: [[f64; 2]; 2],
+    radius: f64,
+    method: PcMethod,
+) -> Result<f64, CollisionError> {
+    // principal axes of the covariance
+    let [[a, b], [_, c]] = covariance;
+    let angle = 0.5 * (2.0 * b).atan2(a - c);
+    let (sin, cos) = angle.sin_cos();
+    let sigma_x = (a * cos * cos + 2.0 * b * sin * cos + c * sin * sin).sqrt();
+    let sigma_z = (a * sin * sin - 2.0 * b * sin * cos + c * cos * cos).sqrt();
+    if !(sigma_x > 0.0 && sigma_z > 0.0) {
+        return Err(CollisionError::InvalidCovariance);
+    }
+    let xm = miss[0] * cos + miss[1] * sin;
+    let zm = -miss[0] * sin + miss[1] * cos;
+
+    let pc = match method {
+        PcMethod::Foster => foster(xm, zm, sigma_x, sigma_z, radius),
+        PcMethod::Chan => chan(xm, zm, sigma_x, sigma_z, radius),
+        PcMethod::Alfano => alfano(xm, zm, sigma_x, sigma_z, radius),
+    };
+    Ok(pc.clamp(0.0, 1.0))
+}
+
+/// Probability of collision at a conjunction from the relative state at the
+/// time of closest approach (km and km/s), the position covariances of the
+/// two objects (km², same frame) and their combined hard-body radius (km).
+pub fn collision_probability(
+    relative_position: &EciVec3,
+    relative_velocity: &EciVec3,
+    covariance1: &Mat3,
+    covariance2: &Mat3,
+    radius: f64,
+    method: PcMethod,
+) -> Result<f64, CollisionError> {
+    let mut covariance = *covariance1;
+    for (row, other) in covariance.iter_mut().zip(covariance2) {
+        for (value, added) in row.iter_mut().zip(other) {
+            *value += added;
+        }
+    }
+    let (miss, projected) = encounter_plane(relative_position, relative_velocity, &covariance)?;
+    collision_probability_2d(miss, projected, radius, method)
+}
+
+// Polar integration over the disc: Simpson's rule along the radius and the
+// trapezoidal rule, which converges fast for periodic integrands, around it.
+fn foster(xm: f64, zm: f64, sigma_x: f64, sigma_z: f64, radius: f64) -> f64 {
+    let n = intervals(radius, sigma_x.min(sigma_z));
+    let m = 2 * n;
+    let h = radius / n as f64;
+    let mut sum = 0.0;
+    for i in 1..=n {
+        let rho = h * i as f64;
+        let weight = if i == n {
+            1.0
+        } else if i % 2 == 1 {
+            4.0
+        } else {
+            2.0
+        };
+        let mut ring = 0.0;
+        for j in 0..m {
+            let (sin, cos) = (TWO_PI * j as f64 / m as f64).sin_cos();
+            let x = (xm + rho * cos) / sigma_x;
+            let z = (zm + rho * sin) / sigma_z;
+            ring += (-0.5 * (x * x + z * z)).exp();
+        }
+        sum += weight * rho * ring * TWO_PI / m as f64;
+    }
+    sum * h / 3.0 / (TWO_PI * sigma_x * sigma_z)
+}
+
+fn chan(xm: f64, zm: f64, sigma_x: f64, sigma_z: f64, radius: f64) -> f64 {
+    let u = radius * radius / (sigma_x * sigma_z);
+    let v = (xm / sigma_x).powi(2) + (zm / sigma_z).powi(2);
+
+    // Poisson weights in v/2, each multiplying the upper tail of a Poisson
+    // distribution in u/2 beyond the same index
+    let mut weight = (-0.5 * v).exp();
+    let mut poisson = (-0.5 * u).exp();
+    let mut tail = -(-0.5 * u).exp_m1();
+    let mut pc = weight * tail;
+    let mut m = 0.0;
+    while m < 0.5 * v || weight > 1e-20 {
+        m += 1.0;
+        weight *= 0.5 * v / m;
+        poisson *= 0.5 * u / m;
+        tail = (tail - poisson).max(0.0);
+        pc += weight * tail;
+        if m > 10000.0 || tail == 0.0 {
+            break;
+        }
+    }
+    pc
+}
+
+// Simpson's rule across the disc of the probability mass in each vertical
+// chord. The chord is parametrised by x = -R cos t, which removes the square
+// root singularity at the edges of the disc.
+fn alfano(xm: f64, zm: f64, sigma_x: f64, sigma_z: f64, radius: f64) -> f64 {
+    let n = intervals(radius, sigma_x.min(sigma_z));
+    let h = PI / n as f64;
+    let scale = std::f64::consts::SQRT_2 * sigma_z;
+    let mut sum = 0.0;
+    for i in 1..n {
+        let (sin, cos) = (h * i as f64).sin_cos();
+        let x = -radius * cos;
+        let chord = radius * sin;
+        let weight = if i % 2 == 1 { 4.0 } else { 2.0 };
+        let density = (-0.5 * ((xm + x) / sigma_x).powi(2)).exp();
+        let mass = erf_interval((zm - chord) / scale, (zm + chord) / scale);
+        sum += weight * density * mass * chord;
+    }
+    sum * h / 3.0 / (TWO_PI.sqrt() * sigma_x)
+}
+
+/// Probability of collision of [`collision_probability`], with each
+/// covariance given as the nine elements of the matrix in row-major order.
+#[wasm_bindgen(js_name = "collisionProbability")]
+pub fn js_collision_probability(
+    relative_position: &EciVec3,
+    relative_velocity: &EciVec3,
+    covariance1: Vec<f64>,
+    covariance2: Vec<f64>,
+    radius: f64,
+    method: PcMethod,
+) -> Result<f64, JsValue> {
+    let matrix = |values: &[f64]| -> Result<Mat3, JsValue> {
+        match values {
+            [a, b, c, d, e, f, g, h, i] => Ok([[*a, *b, *c], [*d, *e, *f], [*g, *h, *i]]),
+            _ => Err(to_value(&CollisionError::InvalidCovariance).unwrap()),
+        }
+    };
+    collision_probability(
+        relative_position,
+        relative_velocity,
+        &matrix(&covariance1)?,
+        &matrix(&covariance2)?,
+        radius,
+        method,
+    )
+    .map_err(|error| to_value(&error).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METHODS: [PcMethod; 3] = [PcMethod::Foster, PcMethod::Chan, PcMethod::Alfano];
+    const RADIUS: f64 = 0.02;
+
+    fn diagonal(x: f64, y: f64, z: f64) -> Mat3 {
+        [[x, 0.0, 0.0], [0.0, y, 0.0], [0.0, 0.0, z]]
+    }
+
+    #[test]
+    fn test_erfc() {
+        assert_eq!(erfc(0.0), 1.0);
+        assert!((erfc(0.5) - 0.479_500_122_186_953_5).abs() < 1e-15);
+        assert!((erfc(-1.0) - 1.842_700_792_949_715).abs() < 1e-15);
+        assert!((erfc(4.0) / 1.541_725_790_028_002e-8 - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_zero_miss_isotropic() {
+        let sigma: f64 = 0.05;
+        let expected = 1.0 - (-RADIUS * RADIUS / (2.0 * sigma * sigma)).exp();
+        let covariance = [[sigma * sigma, 0.0], [0.0, sigma * sigma]];
+        for method in METHODS {
+            let pc = collision_probability_2d([0.0, 0.0], covariance, RADIUS, method).unwrap();
+            assert!((pc / expected - 1.0).abs() < 1e-6, "{:?}: {}", method, pc);
+        }
+    }
+
+    #[test]
+    fn test_isotropic_miss() {
+        // each object contributes half of an isotropic 50 m covariance; the
+        // along-track variance has no effect on the encounter plane
+        let covariance = diagonal(0.00125, 1.0, 0.00125);
+        let position = EciVec3::new(0.1, 0.0, 0.0);
+        let velocity = EciVec3::new(0.0, 10.0, 0.0);
+
+        let chan = collision_probability(
+            &position,
+            &velocity,
+            &covariance,
+            &covariance,
+            RADIUS,
+            PcMethod::Chan,
+        )
+        .unwrap();
+        assert!(chan > 1e-3 && chan < 2e-2);
+        for method in [PcMethod::Foster, PcMethod::Alfano] {
+            let pc = collision_probability(
+                &position,
+                &velocity,
+                &covariance,
+                &covariance,
+                RADIUS,
+                method,
+            )
+            .unwrap();
+            assert!(
+                (pc / chan - 1.0).abs() < 1e-6,
+                "{:?}: {} vs {}",
+                method,
+                pc,
+                chan
+            );
+        }
+    }
+
+    #[test]
+    fn test_anisotropic_miss() {
+        let (sx, sz) = (0.05f64, 0.3f64);
+        let angle = 0.3f64;
+        let (sin, cos) = angle.sin_cos();
+        // covariance with principal axes rotated by `angle` in the plane
+        let covariance = [
+            [
+                sx * sx * cos * cos + sz * sz * sin * sin,
+                (sx * sx - sz * sz) * sin * cos,
+            ],
+            [
+                (sx * sx - sz * sz) * sin * cos,
+                sx * sx * sin * sin + sz * sz * cos * cos,
+            ],
+        ];
+        let miss = [0.06, 0.25];
+
+        let foster = collision_probability_2d(miss, covariance, RADIUS, PcMethod::Foster).unwrap();
+        let alfano = collision_probability_2d(miss, covariance, RADIUS, PcMethod::Alfano).unwrap();
+        let chan = collision_probability_2d(miss, covariance, RADIUS, PcMethod::Chan).unwrap();
+        assert!(foster > 1e-4 && foster < 1e-2);
+        assert!((alfano / foster - 1.0).abs() < 1e-6);
+        assert!((chan / foster - 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_far_miss() {
+        let covariance = [[0.0025, 0.0], [0.0, 0.0025]];
+        for method in METHODS {
+            let pc = collision_probability_2d([2.0, 0.0], covariance, RADIUS, method).unwrap();
+            assert!(pc < 1e-100, "{:?}: {}", method, pc);
+        }
+    }
+
+    #[test]
+    fn test_maximum_probability() {
+        // Alfano, "Relating position uncertainty to maximum conjunction
+        // probability", J. Astronaut. Sci. (2005)
+        let radius: f64 = 0.01;
+        let miss: f64 = 1.0;
+        let expected = radius * radius / (std::f64::consts::E * miss * miss);
+        let pc = |sigma: f64, method: PcMethod| {
+            let covariance = [[sigma * sigma, 0.0], [0.0, sigma * sigma]];
+            collision_probability_2d([miss, 0.0], covariance, radius, method).unwrap()
+        };
+        let worst = miss / 2f64.sqrt();
+        for method in METHODS {
+            assert!(
+                (pc(worst, method) / expected - 1.0).abs() < 1e-3,
+                "{:?}",
+                method
+            );
+            assert!(pc(0.9 * worst, method) < pc(worst, method));
+            assert!(pc(1.1 * worst, method) < pc(worst, method));
+        }
+    }
+
+    #[test]
+    fn test_small_disc_limit() {
+        // leading term of Chan's series, "Spacecraft Collision Probability"
+        // (2008)
+        let radius: f64 = 0.005;
+        let (sx, sz) = (0.2f64, 1.0f64);
+        let (xm, zm) = (0.15, -0.8);
+        let covariance = [[sx * sx, 0.0], [0.0, sz * sz]];
+        let expected = radius * radius / (2.0 * sx * sz)
+            * (-0.5 * ((xm / sx).powi(2) + (zm / sz).powi(2))).exp();
+        for method in METHODS {
+            let pc = collision_probability_2d([xm, zm], covariance, radius, method).unwrap();
+            assert!((pc / expected - 1.0).abs() < 1e-3, "{:?}: {}", method, pc);
+        }
+    }
+
+    #[test]
+    fn test_degenerate_inputs() {
+        let covariance = diagonal(0.0025, 0.0025, 0.0025);
+        let position = EciVec3::new(0.1, 0.0, 0.0);
+        let still = EciVec3::new(0.0, 0.0, 0.0);
+        assert_eq!(
+            collision_probability(
+                &position,
+                &still,
+                &covariance,
+                &covariance,
+                RADIUS,
+                PcMethod::Foster
+            ),
+            Err(CollisionError::ZeroRelativeVelocity)
+        );
+
+        // no uncertainty across the track
+        let flat = [[0.0025, 0.0], [0.0, 0.0]];
+        for method in METHODS {
+            assert_eq!(
+                collision_probability_2d([0.1, 0.0], flat, RADIUS, method),
+                Err(CollisionError::InvalidCovariance)
+            );
+        }
+    }
+}
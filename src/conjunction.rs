@@ -0,0 +1,337 @@
+use crate::constants::EARTH_RADIUS;
+use crate::io::satrecs_from_tles;
+use crate::propagator::{check_range, teme_state, Propagator, PropagatorError, StateVector};
+use crate::search::{find_root, TIME_TOLERANCE};
+use crate::vector::{dot, norm, sub, Vec3};
+use crate::{EciVec3, SatRec};
+use serde::Serialize;
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Margin, in km, added to the perigee/apogee filter of the screen to cover
+/// the difference between the mean radii of the element sets and the
+/// osculating ones.
+const FILTER_PAD: f64 = 25.0;
+
+/// A close approach between the primary and a catalog object. The time is a
+/// Julian date; the relative state is the secondary minus the primary in
+/// TEME, km and km/s.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conjunction {
+    /// Catalog number of the secondary object.
+    pub satnum: String,
+    /// Time of closest approach.
+    pub tca: f64,
+    /// Distance between the objects at the time of closest approach, km.
+    pub miss_distance: f64,
+    /// Relative speed at the time of closest approach, km/s.
+    pub relative_speed: f64,
+    pub relative_position: EciVec3,
+    pub relative_velocity: EciVec3,
+}
+
+/// Result of a conjunction screen.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Screening {
+    /// Close approaches in order of time of closest approach.
+    pub conjunctions: Vec<Conjunction>,
+    /// Catalog numbers of the objects SGP4 failed on within the window,
+    /// which could not be screened.
+    pub failed: Vec<String>,
+}
+
+// Failure while screening one catalog object: one of the primary aborts the
+// screen, one of the secondary only drops that object.
+#[derive(Debug)]
+enum Failure {
+    Primary(PropagatorError),
+    Secondary,
+}
+
+// Position and velocity of `secondary` relative to the primary state `a`
+// at `jd`.
+fn relative_to(a: &StateVector, secondary: &mut SatRec, jd: f64) -> Result<(Vec3, Vec3), Failure> {
+    let b = secondary.state_at(jd).map_err(|_| Failure::Secondary)?;
+    Ok((
+        sub(&b.position().into(), &a.position().into()),
+        sub(&b.velocity().into(), &a.velocity().into()),
+    ))
+}
+
+// Position and velocity of `secondary` relative to `primary` at `jd`.
+fn relative_state<P: Propagator + ?Sized>(
+    primary: &mut P,
+    secondary: &mut SatRec,
+    jd: f64,
+) -> Result<(Vec3, Vec3), Failure> {
+    let a = teme_state(primary, jd).map_err(Failure::Primary)?;
+    relative_to(&a, secondary, jd)
+}
+
+// Zero at a stationary point of the range, negative while the objects close.
+fn range_rate<P: Propagator + ?Sized>(
+    primary: &mut P,
+    secondary: &mut SatRec,
+    jd: f64,
+) -> Result<f64, Failure> {
+    let (position, velocity) = relative_state(primary, secondary, jd)?;
+    Ok(dot(&position, &velocity))
+}
+
+// Close approaches of `secondary` to the primary, whose TEME `states` were
+// sampled at `times`.
+fn secondary_conjunctions<P: Propagator + ?Sized>(
+    primary: &mut P,
+    secondary: &mut SatRec,
+    times: &[f64],
+    states: &[StateVector],
+    threshold: f64,
+) -> Result<Vec<Conjunction>, Failure> {
+    let mut rate_at = |i: usize| -> Result<f64, Failure> {
+        let (position, velocity) = relative_to(&states[i], secondary, times[i])?;
+        Ok(dot(&position, &velocity))
+    };
+    let mut rates = Vec::with_capacity(times.len());
+    for i in 0..times.len() {
+        rates.push(rate_at(i)?);
+    }
+
+    let mut conjunctions = Vec::new();
+    for (i, window) in times.windows(2).enumerate() {
+        if rates[i] < 0.0 && rates[i + 1] >= 0.0 {
+            let tca = find_root(
+                |t| range_rate(primary, secondary, t),
+                window[0],
+                window[1],
+                TIME_TOLERANCE,
+            )?;
+            let (position, velocity) = relative_state(primary, secondary, tca)?;
+            let miss_distance = norm(&position);
+            if miss_distance < threshold {
+                conjunctions.push(Conjunction {
+                    satnum: secondary.satnum(),
+                    tca,
+                    miss_distance,
+                    relative_speed: norm(&velocity),
+                    relative_position: position.into(),
+                    relative_velocity: velocity.into(),
+                });
+            }
+        }
+    }
+    Ok(conjunctions)
+}
+
+/// Screen `primary` against every object in `catalog` for close approaches
+/// closer than `threshold` km between Julian dates `start` and `end`.
+///
+/// The primary can be any [`Propagator`]; it is sampled every `step`
+/// seconds once, and catalog objects whose perigee to apogee shell stays
+/// clear of the range of radii it sweeps are discarded. The others are
+/// sampled at the same times; each interval where the range rate turns from
+/// closing to opening is refined to the time of closest approach by
+/// bisection on the range rate. The step must be short enough that the
+/// range does not go through a minimum and a maximum within one interval, a
+/// minute or so in low Earth orbit. Approaches at the ends of the window are
+/// ignored, and so are catalog entries sharing the primary's catalog number,
+/// when it has one, and entries flying the primary's own orbit, whose range
+/// rate stays zero. Catalog objects SGP4 fails on within the window, such as
+/// decayed element sets, are listed in [`Screening::failed`]; an error of
+/// the primary aborts the screen. Returns
+/// [`PropagatorError::InvalidWindow`] unless `step` is positive and `end` is
+/// after `start`.
+pub fn screen_conjunctions<P: Propagator + ?Sized>(
+    primary: &mut P,
+    catalog: &mut [SatRec],
+    start: f64,
+    end: f64,
+    step: f64,
+    threshold: f64,
+) -> Result<Screening, PropagatorError> {
+    if !(step > 0.0 && end > start) {
+        return Err(PropagatorError::InvalidWindow);
+    }
+    check_range(primary, start, end)?;
+    let satnum = primary.satnum();
+    let n = ((end - start) * 86400.0 / step).ceil().max(1.0) as usize;
+    let times: Vec<f64> = (0..=n)
+        .map(|i| start + (end - start) * (i as f64) / (n as f64))
+        .collect();
+    let states = times
+        .iter()
+        .map(|&t| teme_state(primary, t))
+        .collect::<Result<Vec<_>, _>>()?;
+    let radii: Vec<f64> = states
+        .iter()
+        .map(|state| norm(&state.position().into()))
+        .collect();
+    let min_radius = radii.iter().copied().fold(f64::INFINITY, f64::min);
+    let max_radius = radii.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let pad = threshold + FILTER_PAD;
+    let mut conjunctions = Vec::new();
+    let mut failed = Vec::new();
+
+    for secondary in catalog.iter_mut() {
+        if satnum.as_deref() == Some(secondary.satnum().as_str()) {
+            continue;
+        }
+        let perigee = (secondary.altp + 1.0) * EARTH_RADIUS;
+        let apogee = (secondary.alta + 1.0) * EARTH_RADIUS;
+        if perigee - max_radius > pad || min_radius - apogee > pad {
+            continue;
+        }
+
+        match secondary_conjunctions(primary, secondary, &times, &states, threshold) {
+            Ok(found) => conjunctions.extend(found),
+            Err(Failure::Secondary) => failed.push(secondary.satnum()),
+            Err(Failure::Primary(error)) => return Err(error),
+        }
+    }
+
+    conjunctions.sort_by(|a, b| a.tca.total_cmp(&b.tca));
+    Ok(Screening {
+        conjunctions,
+        failed,
+    })
+}
+
+/// [`screen_conjunctions`] of `primary` against the element sets `tles`, an
+/// array of `[line1, line2]` pairs.
+#[wasm_bindgen(js_name = "screenConjunctions")]
+pub fn js_screen_conjunctions(
+    primary: &mut SatRec,
+    tles: JsValue,
+    start: f64,
+    end: f64,
+    step: f64,
+    threshold: f64,
+) -> Result<JsValue, JsValue> {
+    let mut catalog = satrecs_from_tles(tles)?;
+    match screen_conjunctions(primary, &mut catalog, start, end, step, threshold) {
+        Ok(screening) => Ok(to_value(&screening).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{GravityModel, MINUTES_PER_DAY};
+    use crate::io::twoline2satrec;
+    use crate::transforms::Frame;
+    use crate::KeplerPropagator;
+
+    #[test]
+    fn test_screen_conjunctions() {
+        let line1 = "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993";
+        let mut primary = twoline2satrec(
+            line1,
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        // the same orbit tilted by 0.05°: the two objects meet at the nodes
+        // and drift about 6 km apart in between
+        let mut catalog = vec![
+            twoline2satrec(
+                &line1.replace("25544", "99999"),
+                "2 99999  51.6916 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+            ),
+            twoline2satrec(
+                &line1.replace("25544", "99998"),
+                "2 99998   0.0500  10.0000 0001000   0.0000   0.0000  1.00270000    10",
+            ),
+        ];
+        // an element set SGP4 rejects is reported
+        let mut broken = catalog[0].clone();
+        broken.satnum = "99997".to_string();
+        broken.ecco = 1.5;
+        assert!(broken.state_at(broken.jdsatepoch).is_err());
+        catalog.push(broken);
+        let start = primary.jdsatepoch;
+        let end = start + 0.25;
+
+        let screening =
+            screen_conjunctions(&mut primary, &mut catalog, start, end, 60.0, 1.0).unwrap();
+        assert_eq!(screening.failed, ["99997"]);
+        let conjunctions = screening.conjunctions;
+
+        // two nodal crossings per orbit, close to four orbits
+        assert!(conjunctions.len() >= 7 && conjunctions.len() <= 8);
+        for conjunction in &conjunctions {
+            assert_eq!(conjunction.satnum, "99999");
+            assert!(conjunction.miss_distance < 0.5);
+            let position: Vec3 = (&conjunction.relative_position).into();
+            let velocity: Vec3 = (&conjunction.relative_velocity).into();
+            assert!(dot(&position, &velocity).abs() / conjunction.relative_speed < 1e-3);
+
+            // the range is larger a minute either side
+            for offset in [-1.0, 1.0] {
+                let t = conjunction.tca + offset / MINUTES_PER_DAY;
+                let (nearby, _) = relative_state(&mut primary, &mut catalog[0], t).unwrap();
+                assert!(norm(&nearby) > conjunction.miss_distance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_screen_with_kepler_primary() {
+        let line1 = "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993";
+        let line2 = "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032";
+        let mut iss = twoline2satrec(line1, line2);
+        let start = iss.jdsatepoch;
+        let end = start + 0.1;
+        let mut catalog = vec![
+            iss.clone(),
+            twoline2satrec(
+                &line1.replace("25544", "99999"),
+                "2 99999  51.6916 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+            ),
+        ];
+
+        // a two-body primary built from the ISS element set skips the ISS
+        // itself in the catalog
+        let mut primary = KeplerPropagator::from_satrec(&mut iss, start).unwrap();
+        let conjunctions = screen_conjunctions(&mut primary, &mut catalog, start, end, 60.0, 100.0)
+            .unwrap()
+            .conjunctions;
+        assert!(!conjunctions.is_empty());
+        assert!(conjunctions.iter().all(|c| c.satnum == "99999"));
+
+        // one without a catalog number does not
+        let state = iss.state_at(start).unwrap();
+        let mut anonymous = KeplerPropagator::new(
+            start,
+            state.position(),
+            state.velocity(),
+            Frame::Teme,
+            GravityModel::Wgs72,
+        );
+        let conjunctions =
+            screen_conjunctions(&mut anonymous, &mut catalog, start, end, 60.0, 100.0)
+                .unwrap()
+                .conjunctions;
+        assert!(conjunctions.iter().any(|c| c.satnum == "25544"));
+    }
+
+    #[test]
+    fn test_screen_invalid_window() {
+        let mut primary = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let mut catalog = vec![primary.clone()];
+        let start = primary.jdsatepoch;
+        for (end, step) in [
+            (start + 0.1, 0.0),
+            (start + 0.1, -60.0),
+            (start, 60.0),
+            (start - 0.1, 60.0),
+        ] {
+            assert_eq!(
+                screen_conjunctions(&mut primary, &mut catalog, start, end, step, 1.0).unwrap_err(),
+                PropagatorError::InvalidWindow
+            );
+        }
+    }
+}
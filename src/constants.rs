@@ -1,4 +1,6 @@
+use serde::{Deserialize, Serialize};
 use std::f64::consts;
+use wasm_bindgen::prelude::*;
 
 pub const PI: f64 = consts::PI;
 pub const TWO_PI: f64 = PI * 2.0;
@@ -7,6 +9,8 @@ pub const RAD2DEG: f64 = 180.0 / PI;
 pub const MINUTES_PER_DAY: f64 = 1440.0;
 pub const MU: f64 = 398600.8; // in km^3 / s^2
 pub const EARTH_RADIUS: f64 = 6378.135; // in km
+pub const AU: f64 = 149597870.7; // astronomical unit in km
+pub const OMEGA_EARTH: f64 = 7.292115e-5; // Earth rotation rate in rad / s
 
 pub const XKE: f64 = 0.07436691613317342; // 60.0 / ((EARTH_RADIUS.powi(3) / MU).sqrt())
 pub const VKMPERSEC: f64 = (EARTH_RADIUS * XKE) / 60.0;
@@ -17,6 +21,67 @@ pub const J4: f64 = -0.00000165597;
 pub const J3OJ2: f64 = J3 / J2;
 pub const X2O3: f64 = 2.0 / 3.0;
 
+/// Sets of Earth gravity constants, as in Vallado's `getgravconst`.
+///
+/// SGP4 itself always runs on WGS-72; the other models are for the
+/// analysis tools that take a gravity parameter.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GravityModel {
+    /// WGS-72 with the truncated `xke` of the original SGP4 code.
+    Wgs72Old,
+    Wgs72,
+    Wgs84,
+}
+
+impl GravityModel {
+    /// Gravitational parameter, km^3 / s^2.
+    pub fn mu(&self) -> f64 {
+        match self {
+            GravityModel::Wgs72Old => 398600.79964,
+            GravityModel::Wgs72 => 398600.8,
+            GravityModel::Wgs84 => 398600.5,
+        }
+    }
+
+    /// Equatorial radius, km.
+    pub fn radius(&self) -> f64 {
+        match self {
+            GravityModel::Wgs72Old | GravityModel::Wgs72 => 6378.135,
+            GravityModel::Wgs84 => 6378.137,
+        }
+    }
+
+    /// Reciprocal of the time unit, 1 / min.
+    pub fn xke(&self) -> f64 {
+        match self {
+            GravityModel::Wgs72Old => 0.0743669161,
+            _ => 60.0 / (self.radius().powi(3) / self.mu()).sqrt(),
+        }
+    }
+
+    pub fn j2(&self) -> f64 {
+        match self {
+            GravityModel::Wgs72Old | GravityModel::Wgs72 => 0.001082616,
+            GravityModel::Wgs84 => 0.00108262998905,
+        }
+    }
+
+    pub fn j3(&self) -> f64 {
+        match self {
+            GravityModel::Wgs72Old | GravityModel::Wgs72 => -0.00000253881,
+            GravityModel::Wgs84 => -0.00000253215306,
+        }
+    }
+
+    pub fn j4(&self) -> f64 {
+        match self {
+            GravityModel::Wgs72Old | GravityModel::Wgs72 => -0.00000165597,
+            GravityModel::Wgs84 => -0.00000161098761,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::constants::*;
@@ -37,4 +102,9 @@ mod test {
         assert_eq!(VKMPERSEC, vkmpersec());
         assert_eq!(TUMIN, tumin());
     }
+    #[test]
+    fn test_gravity_model() {
+        assert_eq!(GravityModel::Wgs72.mu(), MU);
+        assert_eq!(GravityModel::Wgs72.xke(), XKE);
+    }
 }
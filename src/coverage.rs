@@ -0,0 +1,283 @@
+use crate::constants::MINUTES_PER_DAY;
+use crate::io::satrecs_from_tles;
+use crate::propagation::gstime::gstime;
+use crate::propagation::sgp4::sgp4;
+use crate::propagator::PropagatorError;
+use crate::transforms::{ecf_to_look_angles, eci_to_ecf};
+use crate::{EcfVec3, GeodeticLocation, SatRec};
+use serde::Serialize;
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::prelude::*;
+
+/// Coverage statistics of one grid point over the analysis window.
+/// Durations are in seconds.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PointCoverage {
+    pub location: GeodeticLocation,
+    /// Percentage of the window during which at least one satellite is above
+    /// the minimum elevation.
+    pub coverage: f64,
+    /// Longest stretch without coverage, including those touching the start
+    /// or the end of the window.
+    pub max_gap: f64,
+    /// Mean time from the end of one access to the start of the next, zero
+    /// when there are fewer than two accesses.
+    pub mean_revisit: f64,
+    /// Number of separate accesses.
+    pub accesses: usize,
+}
+
+/// Most points [`region_grid`] builds; finer spacings are rejected rather
+/// than exhausting memory.
+const MAX_GRID_POINTS: f64 = 1e7;
+
+/// Why a grid could not be built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum GridError {
+    /// The spacing is not a positive finite angle, or is so fine that the
+    /// grid would exceed ten million points.
+    InvalidSpacing,
+    /// The region is not finite or its north edge is south of its south
+    /// edge, or its east edge west of its west edge.
+    InvalidRegion,
+}
+
+// Counts of equal time intervals, covered or not, for one grid point.
+#[derive(Default)]
+struct Tally {
+    covered: usize,
+    accesses: usize,
+    max_gap: usize,
+    // gaps between two accesses, leaving out those at the window edges
+    revisits: Vec<usize>,
+    current_gap: usize,
+    in_access: bool,
+}
+
+impl Tally {
+    fn push(&mut self, covered: bool) {
+        if covered {
+            self.covered += 1;
+            if !self.in_access {
+                if self.accesses > 0 {
+                    self.revisits.push(self.current_gap);
+                }
+                self.accesses += 1;
+                self.max_gap = self.max_gap.max(self.current_gap);
+                self.current_gap = 0;
+            }
+        } else {
+            self.current_gap += 1;
+        }
+        self.in_access = covered;
+    }
+
+    fn finish(self, location: GeodeticLocation, intervals: usize, interval: f64) -> PointCoverage {
+        let max_gap = self.max_gap.max(self.current_gap);
+        let mean_revisit = if self.revisits.is_empty() {
+            0.0
+        } else {
+            self.revisits.iter().sum::<usize>() as f64 / self.revisits.len() as f64
+        };
+        PointCoverage {
+            location,
+            coverage: 100.0 * self.covered as f64 / intervals as f64,
+            max_gap: max_gap as f64 * interval,
+            mean_revisit: mean_revisit * interval,
+            accesses: self.accesses,
+        }
+    }
+}
+
+/// Points of a regular latitude/longitude grid spanning the region from
+/// `south` to `north` and `west` to `east`, every `spacing`. Angles are in
+/// radians; the edges of the region are included.
+pub fn region_grid(
+    south: f64,
+    north: f64,
+    west: f64,
+    east: f64,
+    spacing: f64,
+) -> Result<Vec<GeodeticLocation>, GridError> {
+    if !(spacing > 0.0 && spacing.is_finite()) {
+        return Err(GridError::InvalidSpacing);
+    }
+    if !(north >= south && east >= west && (north - south + east - west).is_finite()) {
+        return Err(GridError::InvalidRegion);
+    }
+    let rows = ((north - south) / spacing + 1e-9).floor();
+    let columns = ((east - west) / spacing + 1e-9).floor();
+    if (rows + 1.0) * (columns + 1.0) > MAX_GRID_POINTS {
+        return Err(GridError::InvalidSpacing);
+    }
+    let (rows, columns) = (rows as usize, columns as usize);
+    let mut grid = Vec::with_capacity((rows + 1) * (columns + 1));
+    for i in 0..=rows {
+        for j in 0..=columns {
+            grid.push(GeodeticLocation::new(
+                west + spacing * j as f64,
+                south + spacing * i as f64,
+                0.0,
+            ));
+        }
+    }
+    Ok(grid)
+}
+
+#[wasm_bindgen(js_name = "regionGrid")]
+pub fn js_region_grid(
+    south: f64,
+    north: f64,
+    west: f64,
+    east: f64,
+    spacing: f64,
+) -> Result<JsValue, JsValue> {
+    match region_grid(south, north, west, east, spacing) {
+        Ok(grid) => Ok(to_value(&grid).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+/// Coverage of `points` by the satellites in `satrecs` from `start` to `end`
+/// (Julian dates). A point is covered when at least one satellite is above
+/// `min_elevation` (radians).
+///
+/// The window is split into equal intervals no longer than `step` seconds,
+/// each taking the visibility at its start, so gaps and accesses shorter
+/// than `step` may be missed. Returns [`PropagatorError::InvalidWindow`]
+/// unless `step` is positive and `end` is after `start`.
+pub fn coverage_analysis(
+    satrecs: &mut [SatRec],
+    points: &[GeodeticLocation],
+    start: f64,
+    end: f64,
+    step: f64,
+    min_elevation: f64,
+) -> Result<Vec<PointCoverage>, PropagatorError> {
+    if step <= 0.0 || end <= start {
+        return Err(PropagatorError::InvalidWindow);
+    }
+    let span = (end - start) * 86400.0;
+    let n = (span / step).ceil().max(1.0) as usize;
+    let mut tallies: Vec<Tally> = points.iter().map(|_| Tally::default()).collect();
+    let mut satellites: Vec<EcfVec3> = Vec::with_capacity(satrecs.len());
+
+    for i in 0..n {
+        let jd = start + (end - start) * (i as f64) / (n as f64);
+        let gmst = gstime(jd);
+        satellites.clear();
+        for satrec in satrecs.iter_mut() {
+            let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+            satellites.push(eci_to_ecf(result.position(), gmst));
+        }
+
+        for (point, tally) in points.iter().zip(tallies.iter_mut()) {
+            let covered = satellites
+                .iter()
+                .any(|satellite| ecf_to_look_angles(point, satellite).elevation() >= min_elevation);
+            tally.push(covered);
+        }
+    }
+
+    Ok(points
+        .iter()
+        .zip(tallies)
+        .map(|(point, tally)| tally.finish(*point, n, span / n as f64))
+        .collect())
+}
+
+/// [`coverage_analysis`] of the element sets `tles`, an array of
+/// `[line1, line2]` pairs, over `points` as returned by `regionGrid`.
+#[wasm_bindgen(js_name = "coverageAnalysis")]
+pub fn js_coverage_analysis(
+    tles: JsValue,
+    points: JsValue,
+    start: f64,
+    end: f64,
+    step: f64,
+    min_elevation: f64,
+) -> Result<JsValue, JsValue> {
+    let mut satrecs = satrecs_from_tles(tles)?;
+    let points: Vec<GeodeticLocation> = from_value(points)?;
+    match coverage_analysis(&mut satrecs, &points, start, end, step, min_elevation) {
+        Ok(coverage) => Ok(to_value(&coverage).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEG2RAD;
+    use crate::ext::jday;
+    use crate::io::twoline2satrec;
+    use crate::passes::find_passes;
+
+    #[test]
+    fn test_coverage_analysis_iss() {
+        let mut satrecs = vec![twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        )];
+        let start = jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+        let end = start + 1.0;
+        let min_elevation = 10.0 * DEG2RAD;
+        let points = region_grid(0.0, 80.0 * DEG2RAD, 0.0, 0.0, 40.0 * DEG2RAD).unwrap();
+        assert_eq!(points.len(), 3);
+
+        let coverage =
+            coverage_analysis(&mut satrecs, &points, start, end, 10.0, min_elevation).unwrap();
+
+        // the coverage matches the passes found over the same point
+        let passes = find_passes(&mut satrecs[0], &points[1], start, end, min_elevation).unwrap();
+        let in_view: f64 = passes.iter().map(|pass| pass.duration()).sum();
+        assert_eq!(coverage[1].accesses, passes.len());
+        assert!((coverage[1].coverage - 100.0 * in_view / 86400.0).abs() < 0.1);
+        assert!(coverage[1].max_gap >= coverage[1].mean_revisit);
+        assert!(coverage[1].max_gap < 86400.0);
+
+        // revisits are the gaps between consecutive passes
+        let revisits: Vec<f64> = passes
+            .windows(2)
+            .map(|pair| (pair[1].aos - pair[0].los) * 86400.0)
+            .collect();
+        let mean_revisit = revisits.iter().sum::<f64>() / revisits.len() as f64;
+        assert!((coverage[1].mean_revisit - mean_revisit).abs() < 10.0);
+
+        // the ISS never climbs 10° above the horizon at 80° latitude: one gap
+        // over exactly the window
+        assert_eq!(coverage[2].coverage, 0.0);
+        assert_eq!(coverage[2].accesses, 0);
+        assert!((coverage[2].max_gap - 86400.0).abs() < 1e-6);
+        assert_eq!(coverage[2].mean_revisit, 0.0);
+
+        for (end, step) in [(end, 0.0), (end, -10.0), (start, 10.0)] {
+            assert_eq!(
+                coverage_analysis(&mut satrecs, &points, start, end, step, min_elevation)
+                    .unwrap_err(),
+                PropagatorError::InvalidWindow
+            );
+        }
+    }
+    #[test]
+    fn test_region_grid() {
+        let grid = region_grid(-0.1, 0.1, 1.0, 1.3, 0.1).unwrap();
+        assert_eq!(grid.len(), 3 * 4);
+        assert!((grid[11].latitude - 0.1).abs() < 1e-12);
+        assert!((grid[11].longitude - 1.3).abs() < 1e-12);
+
+        for spacing in [0.0, -0.1, f64::NAN, f64::INFINITY, 1e-300] {
+            let error = region_grid(0.0, 0.1, 0.0, 0.1, spacing).unwrap_err();
+            assert_eq!(error, GridError::InvalidSpacing);
+        }
+        for (south, north, west, east) in [(0.1, 0.0, 0.0, 0.1), (0.0, 0.1, 0.1, 0.0)] {
+            let error = region_grid(south, north, west, east, 0.01).unwrap_err();
+            assert_eq!(error, GridError::InvalidRegion);
+        }
+        assert_eq!(
+            region_grid(0.0, f64::INFINITY, 0.0, 0.1, 0.01).unwrap_err(),
+            GridError::InvalidRegion
+        );
+    }
+}
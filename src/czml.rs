@@ -0,0 +1,311 @@
+use crate::constants::TWO_PI;
+use crate::io::satrecs_from_tles;
+use crate::oem::format_epoch;
+use crate::propagator::{check_range, ecf_position, teme_state, Propagator, PropagatorError};
+use crate::transforms::teme_to_j2000;
+use crate::SatRec;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::prelude::*;
+
+/// Colours given in turn to the objects of a document, RGBA.
+const PALETTE: [[u8; 4]; 6] = [
+    [255, 255, 0, 255],
+    [0, 255, 255, 255],
+    [255, 0, 255, 255],
+    [0, 255, 0, 255],
+    [255, 128, 0, 255],
+    [255, 255, 255, 255],
+];
+
+/// Frame of the CZML position samples.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CzmlFrame {
+    /// Earth-fixed, for ground-relative views.
+    Fixed,
+    /// Cesium's inertial frame (ICRF), written from J2000.
+    Inertial,
+}
+
+/// Content of generated CZML packets.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CzmlOptions {
+    pub frame: CzmlFrame,
+    /// Sampling step, seconds.
+    pub step: f64,
+    /// Degree of the Lagrange interpolation Cesium applies between samples.
+    pub interpolation_degree: usize,
+    pub show_label: bool,
+    pub show_path: bool,
+    /// Length of the path drawn ahead of and behind each object, seconds.
+    /// When unset, one orbital period for the element sets of a document
+    /// and the whole window for a single packet.
+    pub path_time: Option<f64>,
+}
+
+impl Default for CzmlOptions {
+    fn default() -> CzmlOptions {
+        CzmlOptions {
+            frame: CzmlFrame::Fixed,
+            step: 60.0,
+            interpolation_degree: 5,
+            show_label: true,
+            show_path: true,
+            path_time: None,
+        }
+    }
+}
+
+// Dates beyond the calendar are out of the range of any propagator.
+fn iso(jd: f64) -> Result<String, PropagatorError> {
+    match format_epoch(jd) {
+        Ok(epoch) => Ok(format!("{}Z", epoch)),
+        Err(_) => Err(PropagatorError::OutOfRange),
+    }
+}
+
+fn interval(start: f64, end: f64) -> Result<String, PropagatorError> {
+    Ok(format!("{}/{}", iso(start)?, iso(end)?))
+}
+
+/// The CZML packet of one object: position samples of `propagator`, in
+/// metres, every `options.step` seconds from Julian date `start` to `end`,
+/// with its availability, a point, a label and a path in `color` (RGBA).
+/// The window must not be empty and the step must be positive.
+pub fn czml_packet<P: Propagator + ?Sized>(
+    propagator: &mut P,
+    id: &str,
+    name: &str,
+    start: f64,
+    end: f64,
+    color: [u8; 4],
+    options: &CzmlOptions,
+) -> Result<Value, PropagatorError> {
+    if options.step <= 0.0 || end <= start {
+        return Err(PropagatorError::InvalidWindow);
+    }
+    check_range(propagator, start, end)?;
+    let span = (end - start) * 86400.0;
+    let path_time = options.path_time.unwrap_or(span);
+    let n = (span / options.step).ceil().max(1.0) as usize;
+    let mut cartesian = Vec::with_capacity(4 * (n + 1));
+    for i in 0..=n {
+        let t = start + (end - start) * (i as f64) / (n as f64);
+        let position = match options.frame {
+            CzmlFrame::Fixed => {
+                let ecf = ecf_position(propagator, t)?;
+                [ecf.x, ecf.y, ecf.z]
+            }
+            CzmlFrame::Inertial => {
+                let j2000 = teme_to_j2000(teme_state(propagator, t)?.position(), t);
+                [j2000.x, j2000.y, j2000.z]
+            }
+        };
+        cartesian.push(span * (i as f64) / (n as f64));
+        cartesian.extend(position.iter().map(|km| km * 1000.0));
+    }
+
+    let rgba = json!({ "rgba": color });
+    Ok(json!({
+        "id": id,
+        "name": name,
+        "availability": interval(start, end)?,
+        "point": {
+            "pixelSize": 6,
+            "color": rgba,
+        },
+        "label": {
+            "show": options.show_label,
+            "text": name,
+            "font": "11pt sans-serif",
+            "fillColor": rgba,
+            "horizontalOrigin": "LEFT",
+            "pixelOffset": { "cartesian2": [10, 0] },
+        },
+        "path": {
+            "show": options.show_path,
+            "width": 1,
+            "resolution": options.step,
+            "leadTime": path_time,
+            "trailTime": path_time,
+            "material": { "solidColor": { "color": rgba } },
+        },
+        "position": {
+            "referenceFrame": match options.frame {
+                CzmlFrame::Fixed => "FIXED",
+                CzmlFrame::Inertial => "INERTIAL",
+            },
+            "interpolationAlgorithm": "LAGRANGE",
+            "interpolationDegree": options.interpolation_degree,
+            "epoch": iso(start)?,
+            "cartesian": cartesian,
+        },
+    }))
+}
+
+/// A CZML document animating `satrecs` from Julian date `start` to `end`:
+/// a clock over the window followed by one packet per element set, named
+/// after `names` or, past its end, the catalog number. Packet ids join the
+/// catalog number and the position in `satrecs`, so repeated element sets
+/// of one object stay distinct.
+pub fn czml_document(
+    satrecs: &mut [SatRec],
+    names: &[String],
+    start: f64,
+    end: f64,
+    options: &CzmlOptions,
+) -> Result<Value, PropagatorError> {
+    let mut document = vec![json!({
+        "id": "document",
+        "name": "satellite",
+        "version": "1.0",
+        "clock": {
+            "interval": interval(start, end)?,
+            "currentTime": iso(start)?,
+            "multiplier": 60,
+            "range": "LOOP_STOP",
+            "step": "SYSTEM_CLOCK_MULTIPLIER",
+        },
+    })];
+    for (i, satrec) in satrecs.iter_mut().enumerate() {
+        let satnum = satrec.satnum();
+        let name = names.get(i).unwrap_or(&satnum);
+        let id = format!("{}-{}", satnum, i);
+        let options = CzmlOptions {
+            path_time: Some(options.path_time.unwrap_or(TWO_PI / satrec.no * 60.0)),
+            ..*options
+        };
+        document.push(czml_packet(
+            satrec,
+            &id,
+            name,
+            start,
+            end,
+            PALETTE[i % PALETTE.len()],
+            &options,
+        )?);
+    }
+    Ok(Value::Array(document))
+}
+
+/// The CZML document of [`czml_document`] as a JSON string, ready for
+/// `Cesium.CzmlDataSource.load(JSON.parse(...))`. The element sets `tles`
+/// are an array of `[line1, line2]` pairs.
+#[wasm_bindgen(js_name = "czmlDocument")]
+pub fn js_czml_document(
+    tles: JsValue,
+    names: Vec<String>,
+    start: f64,
+    end: f64,
+    options: JsValue,
+) -> Result<String, JsValue> {
+    let mut satrecs = satrecs_from_tles(tles)?;
+    let options: CzmlOptions = if options.is_undefined() || options.is_null() {
+        CzmlOptions::default()
+    } else {
+        from_value(options)?
+    };
+    match czml_document(&mut satrecs, &names, start, end, &options) {
+        Ok(document) => Ok(document.to_string()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::twoline2satrec;
+    use crate::propagation::gstime::gstime;
+    use crate::transforms::eci_to_ecf;
+
+    fn iss() -> SatRec {
+        twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        )
+    }
+
+    #[test]
+    fn test_czml_document() {
+        let mut satrecs = vec![iss(), iss()];
+        let start = satrecs[0].jdsatepoch;
+        let end = start + 0.25;
+        let names = vec!["ISS".to_string()];
+
+        let document =
+            czml_document(&mut satrecs, &names, start, end, &CzmlOptions::default()).unwrap();
+
+        let packets = document.as_array().unwrap();
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0]["id"], "document");
+        assert_eq!(
+            packets[0]["clock"]["currentTime"],
+            "2024-01-01T12:00:00.000000Z"
+        );
+        assert_eq!(packets[1]["name"], "ISS");
+        assert_eq!(packets[2]["name"], "25544");
+        assert_eq!(packets[1]["id"], "25544-0");
+        assert_eq!(packets[2]["id"], "25544-1");
+        assert_eq!(
+            packets[1]["availability"],
+            "2024-01-01T12:00:00.000000Z/2024-01-01T18:00:00.000000Z"
+        );
+
+        let position = &packets[1]["position"];
+        assert_eq!(position["referenceFrame"], "FIXED");
+        assert_eq!(position["interpolationAlgorithm"], "LAGRANGE");
+        let cartesian = position["cartesian"].as_array().unwrap();
+        // six hours at one minute, four numbers per sample
+        assert_eq!(cartesian.len(), 4 * 361);
+        assert!((cartesian[4].as_f64().unwrap() - 60.0).abs() < 1e-3);
+
+        let state = iss().state_at(start).unwrap();
+        let ecf = eci_to_ecf(state.position(), gstime(start));
+        assert!((cartesian[1].as_f64().unwrap() - ecf.x * 1000.0).abs() < 1e-3);
+
+        // a path of one orbit ahead and behind
+        let lead = packets[1]["path"]["leadTime"].as_f64().unwrap();
+        assert!((lead / 60.0 - 92.9).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_inertial_packet() {
+        let mut satrec = iss();
+        let start = satrec.jdsatepoch;
+        let options = CzmlOptions {
+            frame: CzmlFrame::Inertial,
+            step: 300.0,
+            ..CzmlOptions::default()
+        };
+
+        let packet = czml_packet(
+            &mut satrec,
+            "a",
+            "b",
+            start,
+            start + 0.1,
+            [1, 2, 3, 4],
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(packet["position"]["referenceFrame"], "INERTIAL");
+        assert!((packet["path"]["trailTime"].as_f64().unwrap() - 8640.0).abs() < 1e-3);
+        assert_eq!(packet["point"]["color"]["rgba"], json!([1, 2, 3, 4]));
+        let cartesian = packet["position"]["cartesian"].as_array().unwrap();
+        let expected = teme_to_j2000(satrec.state_at(start).unwrap().position(), start);
+        assert!((cartesian[3].as_f64().unwrap() - expected.z * 1000.0).abs() < 1e-3);
+
+        for step in [0.0, -60.0] {
+            let options = CzmlOptions {
+                step,
+                ..CzmlOptions::default()
+            };
+            let error = czml_packet(&mut satrec, "a", "b", start, start + 0.1, [0; 4], &options);
+            assert_eq!(error.err(), Some(PropagatorError::InvalidWindow));
+        }
+    }
+}
@@ -1,18 +1,18 @@
-use crate::EciVec3;
+use crate::constants::{MINUTES_PER_DAY, OMEGA_EARTH};
+use crate::propagation::gstime::gstime;
+use crate::propagation::sgp4::sgp4;
+use crate::propagator::PropagatorError;
+use crate::transforms::{ecf_to_eci, geodetic_to_ecf};
+use crate::{EciVec3, GeodeticLocation, SatRec};
+use serde::Serialize;
+use serde_wasm_bindgen::to_value;
 use wasm_bindgen::prelude::*;
-fn sign(value: f64) -> f64 {
-    if value >= 0.0 {
-        1.0
-    } else {
-        -1.0
-    }
-}
-// 计算多普勒因子
-#[wasm_bindgen(js_name = "dopplerFactor")]
-pub fn doppler_factor(location: &EciVec3, position: &EciVec3, velocity: &EciVec3) -> f64 {
-    const M_FACTOR: f64 = 7.292115E-5; // 地球自转角速度
-    const LIGHT_SPEED: f64 = 299792.458; // 光速 km/s
 
+const LIGHT_SPEED: f64 = 299792.458; // 光速 km/s
+
+// Range rate in km/s of a satellite seen from an observer fixed to the
+// rotating Earth, all vectors in the same inertial frame.
+fn range_rate(location: &EciVec3, position: &EciVec3, velocity: &EciVec3) -> f64 {
     let range = EciVec3 {
         x: position.x - location.x,
         y: position.y - location.y,
@@ -20,14 +20,96 @@ pub fn doppler_factor(location: &EciVec3, position: &EciVec3, velocity: &EciVec3
     };
     let range_w = (range.x.powi(2) + range.y.powi(2) + range.z.powi(2)).sqrt();
     let range_vel = EciVec3 {
-        x: velocity.x + M_FACTOR * location.y,
-        y: velocity.y - M_FACTOR * location.x,
+        x: velocity.x + OMEGA_EARTH * location.y,
+        y: velocity.y - OMEGA_EARTH * location.x,
         z: velocity.z,
     };
-    let range_rate =
-        (range.x * range_vel.x + range.y * range_vel.y + range.z * range_vel.z) / range_w;
+    (range.x * range_vel.x + range.y * range_vel.y + range.z * range_vel.z) / range_w
+}
 
-    return (1.0 + (range_rate / LIGHT_SPEED)) * sign(range_rate);
+/// 计算多普勒因子
+///
+/// Ratio of the transmitted to the received frequency, `1 + range_rate / c`:
+/// divide a satellite's carrier by it to get the frequency heard on the
+/// ground. The observer `location`, satellite `position` and `velocity` are
+/// in the same inertial frame (km, km/s); convert an Earth-fixed observer
+/// with `ecf_to_eci` first.
+#[wasm_bindgen(js_name = "dopplerFactor")]
+pub fn doppler_factor(location: &EciVec3, position: &EciVec3, velocity: &EciVec3) -> f64 {
+    1.0 + (range_rate(location, position, velocity) / LIGHT_SPEED)
+}
+
+/// Doppler shift of a radio carrier at one instant.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DopplerSample {
+    /// Julian date.
+    pub jd: f64,
+    /// Range rate in km/s, positive while the satellite moves away.
+    pub range_rate: f64,
+    /// Frequency received on the ground for a carrier sent by the satellite, Hz.
+    pub downlink_frequency: f64,
+    /// `downlink_frequency` minus the carrier, Hz.
+    pub downlink_offset: f64,
+    /// Frequency to transmit from the ground for the satellite to receive
+    /// the carrier, Hz.
+    pub uplink_frequency: f64,
+    /// `uplink_frequency` minus the carrier, Hz.
+    pub uplink_offset: f64,
+}
+
+/// Doppler tuning curve for a carrier of `frequency` Hz between `satrec` and
+/// `observer`, sampled every `step` seconds from `start` to `end` (Julian
+/// dates). Returns [`PropagatorError::InvalidWindow`] unless `step` is
+/// positive and `end` is not before `start`.
+pub fn doppler_curve(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    frequency: f64,
+    start: f64,
+    end: f64,
+    step: f64,
+) -> Result<Vec<DopplerSample>, PropagatorError> {
+    if step <= 0.0 || end < start {
+        return Err(PropagatorError::InvalidWindow);
+    }
+    let observer_ecf = geodetic_to_ecf(observer);
+    let n = ((end - start) * 86400.0 / step).floor().max(0.0) as usize;
+
+    let mut samples = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        let jd = start + (step * i as f64) / 86400.0;
+        let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+        let location = ecf_to_eci(&observer_ecf, gstime(jd));
+        let rate = range_rate(&location, result.position(), result.velocity());
+
+        let downlink_frequency = frequency * (1.0 - rate / LIGHT_SPEED);
+        let uplink_frequency = frequency / (1.0 - rate / LIGHT_SPEED);
+        samples.push(DopplerSample {
+            jd,
+            range_rate: rate,
+            downlink_frequency,
+            downlink_offset: downlink_frequency - frequency,
+            uplink_frequency,
+            uplink_offset: uplink_frequency - frequency,
+        });
+    }
+    Ok(samples)
+}
+
+#[wasm_bindgen(js_name = "dopplerCurve")]
+pub fn js_doppler_curve(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    frequency: f64,
+    start: f64,
+    end: f64,
+    step: f64,
+) -> Result<JsValue, JsValue> {
+    match doppler_curve(satrec, observer, frequency, start, end, step) {
+        Ok(samples) => Ok(to_value(&samples).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
 }
 
 #[cfg(test)]
@@ -62,4 +144,49 @@ mod tests {
             "Doppler factor is not close to 1.0"
         );
     }
+
+    #[test]
+    fn test_doppler_factor_approaching() {
+        let observer = EciVec3::new(0.0, 0.0, EARTH_RADIUS);
+        let position = EciVec3::new(1000.0, 0.0, EARTH_RADIUS);
+        let velocity = EciVec3::new(-7.0, 0.0, 0.0);
+
+        let dop_factor = doppler_factor(&observer, &position, &velocity);
+
+        assert!((dop_factor - (1.0 - 7.0 / LIGHT_SPEED)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_doppler_curve_over_pass() {
+        let mut satrec = crate::io::twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let observer = GeodeticLocation::from_degrees(-0.1276, 51.5072, 0.0);
+        let start = crate::ext::jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+        let pass =
+            crate::passes::find_passes(&mut satrec, &observer, start, start + 1.0, 0.2).unwrap()[0];
+        let frequency = 437.8e6;
+
+        let curve =
+            doppler_curve(&mut satrec, &observer, frequency, pass.aos, pass.los, 5.0).unwrap();
+
+        let first = curve.first().unwrap();
+        let last = curve.last().unwrap();
+        // approaching at rise, receding at set, at most v / c * f apart
+        assert!(first.downlink_offset > 5e3 && first.uplink_offset < -5e3);
+        assert!(last.downlink_offset < -5e3 && last.uplink_offset > 5e3);
+        for sample in &curve {
+            assert!(sample.downlink_offset.abs() < 7.8 / LIGHT_SPEED * frequency);
+        }
+
+        assert_eq!(
+            doppler_curve(&mut satrec, &observer, frequency, pass.aos, pass.los, 0.0).unwrap_err(),
+            PropagatorError::InvalidWindow
+        );
+        assert_eq!(
+            doppler_curve(&mut satrec, &observer, frequency, pass.los, pass.aos, 5.0).unwrap_err(),
+            PropagatorError::InvalidWindow
+        );
+    }
 }
@@ -0,0 +1,264 @@
+use crate::constants::{EARTH_RADIUS, MINUTES_PER_DAY, PI, TWO_PI};
+use crate::propagation::sgp4::{sgp4, Sgp4Error};
+use crate::search::{find_root, TIME_TOLERANCE};
+use crate::sun::sun_position_teme;
+use crate::vector::{dot, norm, sub, Vec3};
+use crate::{EciVec3, SatRec};
+use serde::Serialize;
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Radius of the Sun's photosphere in km (IAU 2015 nominal).
+const SUN_RADIUS: f64 = 695700.0;
+
+/// Longest coarse sampling step, in minutes, used to bracket shadow crossings.
+const MAX_STEP_MINUTES: f64 = 1.0;
+
+/// Illumination of a satellite by the Sun.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum EclipseState {
+    Sunlit,
+    /// Part of the solar disc is hidden by the Earth.
+    Penumbra,
+    /// The whole solar disc is hidden by the Earth.
+    Umbra,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eclipse {
+    pub state: EclipseState,
+    /// Fraction of the solar disc hidden by the Earth, 0 (sunlit) .. 1 (umbra).
+    pub shadow_fraction: f64,
+}
+
+/// A passage through the Earth's shadow. Times are Julian dates clipped to
+/// the search window.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EclipseInterval {
+    /// Entry into the penumbra.
+    pub entry: f64,
+    /// Entry into the umbra, if the satellite reaches it.
+    pub umbra_entry: Option<f64>,
+    /// Exit from the umbra, if the satellite reaches it.
+    pub umbra_exit: Option<f64>,
+    /// Exit from the penumbra.
+    pub exit: f64,
+}
+
+impl EclipseInterval {
+    /// Duration of the whole eclipse, penumbra included, in seconds.
+    pub fn duration(&self) -> f64 {
+        (self.exit - self.entry) * 86400.0
+    }
+}
+
+// Apparent radii of the Sun (a) and the Earth (b) seen from the satellite and
+// the angle between their centres (c), radians. Montenbruck & Gill, sec. 3.4.
+fn shadow_geometry(position: &Vec3, sun: &Vec3) -> (f64, f64, f64) {
+    let to_sun = sub(sun, position);
+    let sun_distance = norm(&to_sun);
+    let distance = norm(position);
+    let a = (SUN_RADIUS / sun_distance).asin();
+    let b = (EARTH_RADIUS / distance).min(1.0).asin();
+    let c = (-dot(position, &to_sun) / (distance * sun_distance))
+        .clamp(-1.0, 1.0)
+        .acos();
+    (a, b, c)
+}
+
+/// Angular margin outside the penumbra and the umbra, radians; each is
+/// negative while the satellite is inside that part of the shadow.
+pub(crate) fn shadow_margins(position: &Vec3, sun: &Vec3) -> (f64, f64) {
+    let (a, b, c) = shadow_geometry(position, sun);
+    (c - (a + b), c - (b - a))
+}
+
+/// Eclipse state of a satellite at TEME `position` (km) and Julian date `jd`,
+/// using a conical shadow model with a spherical Earth.
+#[wasm_bindgen]
+pub fn eclipse(position: &EciVec3, jd: f64) -> Eclipse {
+    let (a, b, c) = shadow_geometry(&position.into(), &(&sun_position_teme(jd)).into());
+
+    let (state, shadow_fraction) = if c >= a + b {
+        (EclipseState::Sunlit, 0.0)
+    } else if c < b - a {
+        (EclipseState::Umbra, 1.0)
+    } else if c < a - b {
+        // the Earth's disc lies entirely inside the Sun's
+        (EclipseState::Penumbra, (b * b) / (a * a))
+    } else {
+        let x = (c * c + a * a - b * b) / (2.0 * c);
+        let y = (a * a - x * x).max(0.0).sqrt();
+        let area = a * a * (x / a).clamp(-1.0, 1.0).acos()
+            + b * b * ((c - x) / b).clamp(-1.0, 1.0).acos()
+            - c * y;
+        (
+            EclipseState::Penumbra,
+            (area / (PI * a * a)).clamp(0.0, 1.0),
+        )
+    };
+
+    Eclipse {
+        state,
+        shadow_fraction,
+    }
+}
+
+fn margins_at(satrec: &mut SatRec, jd: f64) -> Result<(f64, f64), Sgp4Error> {
+    let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+    Ok(shadow_margins(
+        &result.position().into(),
+        &(&sun_position_teme(jd)).into(),
+    ))
+}
+
+/// Find the passages of `satrec` through the Earth's shadow between Julian
+/// dates `start` and `end`.
+///
+/// Shadow boundaries are bracketed by sampling at a fraction of the orbital
+/// period and refined by bisection to about 10 ms.
+pub fn find_eclipses(
+    satrec: &mut SatRec,
+    start: f64,
+    end: f64,
+) -> Result<Vec<EclipseInterval>, Sgp4Error> {
+    let mut intervals = Vec::new();
+    if end <= start {
+        return Ok(intervals);
+    }
+
+    let step = ((TWO_PI / satrec.no) / 120.0).min(MAX_STEP_MINUTES) / MINUTES_PER_DAY;
+    let n = ((end - start) / step).ceil().max(1.0) as usize;
+
+    let mut current: Option<EclipseInterval> = None;
+    let mut t0 = start;
+    let (mut penumbra0, mut umbra0) = margins_at(satrec, start)?;
+    if penumbra0 < 0.0 {
+        current = Some(EclipseInterval {
+            entry: start,
+            umbra_entry: if umbra0 < 0.0 { Some(start) } else { None },
+            umbra_exit: None,
+            exit: end,
+        });
+    }
+
+    for i in 1..=n {
+        let t1 = start + (end - start) * (i as f64) / (n as f64);
+        let (penumbra1, umbra1) = margins_at(satrec, t1)?;
+
+        if penumbra0 >= 0.0 && penumbra1 < 0.0 {
+            let entry = find_root(
+                |t| margins_at(satrec, t).map(|m| m.0),
+                t0,
+                t1,
+                TIME_TOLERANCE,
+            )?;
+            current = Some(EclipseInterval {
+                entry,
+                umbra_entry: None,
+                umbra_exit: None,
+                exit: end,
+            });
+        }
+        if let Some(interval) = current.as_mut() {
+            if umbra0 >= 0.0 && umbra1 < 0.0 {
+                let umbra_entry = find_root(
+                    |t| margins_at(satrec, t).map(|m| m.1),
+                    t0,
+                    t1,
+                    TIME_TOLERANCE,
+                )?;
+                interval.umbra_entry = Some(umbra_entry);
+            }
+            if umbra0 < 0.0 && umbra1 >= 0.0 {
+                let umbra_exit = find_root(
+                    |t| margins_at(satrec, t).map(|m| m.1),
+                    t0,
+                    t1,
+                    TIME_TOLERANCE,
+                )?;
+                interval.umbra_exit = Some(umbra_exit);
+            }
+        }
+        if penumbra0 < 0.0 && penumbra1 >= 0.0 {
+            if let Some(mut interval) = current.take() {
+                interval.exit = find_root(
+                    |t| margins_at(satrec, t).map(|m| m.0),
+                    t0,
+                    t1,
+                    TIME_TOLERANCE,
+                )?;
+                intervals.push(interval);
+            }
+        }
+
+        t0 = t1;
+        penumbra0 = penumbra1;
+        umbra0 = umbra1;
+    }
+
+    if let Some(mut interval) = current {
+        if interval.umbra_entry.is_some() && interval.umbra_exit.is_none() {
+            interval.umbra_exit = Some(end);
+        }
+        intervals.push(interval);
+    }
+
+    Ok(intervals)
+}
+
+#[wasm_bindgen(js_name = "findEclipses")]
+pub fn js_find_eclipses(satrec: &mut SatRec, start: f64, end: f64) -> Result<JsValue, JsValue> {
+    match find_eclipses(satrec, start, end) {
+        Ok(intervals) => Ok(to_value(&intervals).unwrap()),
+        Err(sgp4_error) => Err(to_value(&sgp4_error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::jday;
+    use crate::io::twoline2satrec;
+
+    #[test]
+    fn test_eclipse_states() {
+        let jd = jday(2024.0, 6.0, 20.0, 0.0, 0.0, 0.0, 0.0);
+        let sun: Vec3 = (&sun_position_teme(jd)).into();
+        let k = 7000.0 / norm(&sun);
+
+        let day = eclipse(&EciVec3::new(sun[0] * k, sun[1] * k, sun[2] * k), jd);
+        let night = eclipse(&EciVec3::new(-sun[0] * k, -sun[1] * k, -sun[2] * k), jd);
+        assert_eq!(day.state, EclipseState::Sunlit);
+        assert_eq!(day.shadow_fraction, 0.0);
+        assert_eq!(night.state, EclipseState::Umbra);
+        assert_eq!(night.shadow_fraction, 1.0);
+    }
+
+    #[test]
+    fn test_find_eclipses_iss() {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let start = jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+
+        let intervals = find_eclipses(&mut satrec, start, start + 0.5).unwrap();
+
+        // about 7.7 orbits in half a day, each with an eclipse of 30 to 37 minutes
+        assert!(intervals.len() >= 7 && intervals.len() <= 9);
+        for interval in &intervals[1..intervals.len() - 1] {
+            let umbra_entry = interval.umbra_entry.unwrap();
+            let umbra_exit = interval.umbra_exit.unwrap();
+            assert!(interval.entry < umbra_entry && umbra_exit < interval.exit);
+            assert!(interval.duration() > 25.0 * 60.0 && interval.duration() < 40.0 * 60.0);
+            // penumbral transits of a LEO satellite last a few seconds
+            let penumbra = (umbra_entry - interval.entry) * 86400.0;
+            assert!(penumbra > 2.0 && penumbra < 20.0);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::constants::{GravityModel, PI, TWO_PI};
+use crate::propagator::StateVector;
+use crate::vector::{cross, dot, mat_mul, mat_vec, norm, rot1, rot3, Vec3};
+use crate::EciVec3;
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::prelude::*;
+
+/// Threshold below which eccentricities, inclinations and magnitudes are
+/// treated as zero.
+const SMALL: f64 = 1e-8;
+
+/// Osculating classical orbital elements. Distances are in km, angles in
+/// radians.
+///
+/// Elements that are undefined for the orbit type are NaN: the node for
+/// equatorial orbits, the argument of perigee and the true anomaly for
+/// circular or equatorial ones. The alternate angles `arglat`, `truelon`
+/// and `lonper` stand in for them and are only defined in those cases.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassicalElements {
+    /// Semi-latus rectum.
+    pub p: f64,
+    /// Semi-major axis, negative for hyperbolic and infinite for parabolic
+    /// orbits.
+    pub a: f64,
+    pub ecc: f64,
+    pub incl: f64,
+    /// Right ascension of the ascending node.
+    pub raan: f64,
+    /// Argument of perigee.
+    pub argp: f64,
+    /// True anomaly.
+    pub nu: f64,
+    /// Mean anomaly, or its parabolic and hyperbolic counterparts.
+    pub m: f64,
+    /// Argument of latitude, for circular inclined orbits.
+    pub arglat: f64,
+    /// True longitude, for circular equatorial orbits.
+    pub truelon: f64,
+    /// Longitude of perigee, for elliptical equatorial orbits.
+    pub lonper: f64,
+}
+
+// Angle between two vectors, 0 .. pi.
+fn angle(a: &Vec3, b: &Vec3) -> f64 {
+    (dot(a, b) / (norm(a) * norm(b))).clamp(-1.0, 1.0).acos()
+}
+
+// Eccentric (or parabolic, hyperbolic) and mean anomaly from the true
+// anomaly, Vallado's `newtonnu`.
+fn newtonnu(ecc: f64, nu: f64) -> (f64, f64) {
+    let (mut e0, mut m) = (f64::NAN, f64::NAN);
+    if ecc.abs() < SMALL {
+        e0 = nu;
+        m = nu;
+    } else if ecc < 1.0 - SMALL {
+        let sine = ((1.0 - ecc * ecc).sqrt() * nu.sin()) / (1.0 + ecc * nu.cos());
+        let cose = (ecc + nu.cos()) / (1.0 + ecc * nu.cos());
+        e0 = sine.atan2(cose);
+        m = e0 - ecc * e0.sin();
+    } else if ecc > 1.0 + SMALL {
+        if nu.abs() + 0.00001 < PI - (1.0 / ecc).acos() {
+            let sine = ((ecc * ecc - 1.0).sqrt() * nu.sin()) / (1.0 + ecc * nu.cos());
+            e0 = sine.asinh();
+            m = ecc * e0.sinh() - e0;
+        }
+    } else if nu.abs() < 168.0 * PI / 180.0 {
+        e0 = (0.5 * nu).tan();
+        m = e0 + e0 * e0 * e0 / 3.0;
+    }
+
+    if ecc < 1.0 {
+        m = m.rem_euclid(TWO_PI);
+        e0 = e0.rem_euclid(TWO_PI);
+    }
+    (e0, m)
+}
+
+/// Classical orbital elements from an inertial `position` (km) and
+/// `velocity` (km/s), after Vallado's `rv2coe`.
+///
+/// Circular and equatorial orbits are detected and reported through the
+/// alternate angles; every element is NaN when the angular momentum
+/// vanishes.
+pub fn rv2coe(position: &EciVec3, velocity: &EciVec3, gravity: GravityModel) -> ClassicalElements {
+    let mu = gravity.mu();
+    let r: Vec3 = position.into();
+    let v: Vec3 = velocity.into();
+    let mut elements = ClassicalElements {
+        p: f64::NAN,
+        a: f64::NAN,
+        ecc: f64::NAN,
+        incl: f64::NAN,
+        raan: f64::NAN,
+        argp: f64::NAN,
+        nu: f64::NAN,
+        m: f64::NAN,
+        arglat: f64::NAN,
+        truelon: f64::NAN,
+        lonper: f64::NAN,
+    };
+
+    let magr = norm(&r);
+    let magv = norm(&v);
+    let hbar = cross(&r, &v);
+    let magh = norm(&hbar);
+    if magh < SMALL {
+        return elements;
+    }
+
+    let nbar = [-hbar[1], hbar[0], 0.0];
+    let magn = norm(&nbar);
+    let c1 = magv * magv - mu / magr;
+    let rdotv = dot(&r, &v);
+    let ebar: Vec3 = [0, 1, 2].map(|i| (c1 * r[i] - rdotv * v[i]) / mu);
+    let ecc = norm(&ebar);
+
+    let sme = 0.5 * magv * magv - mu / magr;
+    elements.a = if sme.abs() > SMALL {
+        -mu / (2.0 * sme)
+    } else {
+        f64::INFINITY
+    };
+    elements.p = magh * magh / mu;
+    elements.ecc = ecc;
+    let incl = (hbar[2] / magh).clamp(-1.0, 1.0).acos();
+    elements.incl = incl;
+
+    let circular = ecc < SMALL;
+    let equatorial = incl < SMALL || (incl - PI).abs() < SMALL;
+
+    if magn > SMALL {
+        let raan = (nbar[0] / magn).clamp(-1.0, 1.0).acos();
+        elements.raan = if nbar[1] < 0.0 { TWO_PI - raan } else { raan };
+    }
+
+    if !circular && !equatorial {
+        let argp = angle(&nbar, &ebar);
+        elements.argp = if ebar[2] < 0.0 { TWO_PI - argp } else { argp };
+    }
+
+    if !circular {
+        let nu = angle(&ebar, &r);
+        elements.nu = if rdotv < 0.0 { TWO_PI - nu } else { nu };
+    }
+
+    if circular && !equatorial {
+        let arglat = angle(&nbar, &r);
+        elements.arglat = if r[2] < 0.0 { TWO_PI - arglat } else { arglat };
+        elements.m = elements.arglat;
+    }
+
+    if !circular && equatorial {
+        let mut lonper = (ebar[0] / ecc).clamp(-1.0, 1.0).acos();
+        if ebar[1] < 0.0 {
+            lonper = TWO_PI - lonper;
+        }
+        if incl > 0.5 * PI {
+            lonper = TWO_PI - lonper;
+        }
+        elements.lonper = lonper;
+    }
+
+    if circular && equatorial && magr > SMALL {
+        let mut truelon = (r[0] / magr).clamp(-1.0, 1.0).acos();
+        if r[1] < 0.0 {
+            truelon = TWO_PI - truelon;
+        }
+        if incl > 0.5 * PI {
+            truelon = TWO_PI - truelon;
+        }
+        elements.truelon = truelon;
+        elements.m = truelon;
+    }
+
+    if !circular {
+        elements.m = newtonnu(ecc, elements.nu).1;
+    }
+
+    elements
+}
+
+/// Inertial position (km) and velocity (km/s) from classical orbital
+/// elements, after Vallado's `coe2rv`.
+///
+/// Only the angles that define the orbit type are read: `arglat` for
+/// circular inclined orbits, `truelon` for circular equatorial ones and
+/// `lonper` for elliptical equatorial ones; `a` and `m` are ignored.
+pub fn coe2rv(elements: &ClassicalElements, gravity: GravityModel) -> StateVector {
+    let mu = gravity.mu();
+    let ecc = elements.ecc;
+    let incl = elements.incl;
+    let equatorial = incl < SMALL || (incl - PI).abs() < SMALL;
+    let (mut raan, mut argp, mut nu) = (elements.raan, elements.argp, elements.nu);
+
+    if ecc < SMALL {
+        argp = 0.0;
+        if equatorial {
+            raan = 0.0;
+            nu = elements.truelon;
+        } else {
+            nu = elements.arglat;
+        }
+    } else if equatorial {
+        argp = elements.lonper;
+        raan = 0.0;
+    }
+
+    let (sinnu, cosnu) = nu.sin_cos();
+    let radius = elements.p / (1.0 + ecc * cosnu);
+    let rpqw = [radius * cosnu, radius * sinnu, 0.0];
+    let p = elements.p.max(0.0001);
+    let speed = (mu / p).sqrt();
+    let vpqw = [-sinnu * speed, (ecc + cosnu) * speed, 0.0];
+
+    // perifocal to inertial: rotate back through the argument of perigee,
+    // the inclination and the node
+    let m = mat_mul(&mat_mul(&rot3(-raan), &rot1(-incl)), &rot3(-argp));
+    StateVector::new(mat_vec(&m, &rpqw).into(), mat_vec(&m, &vpqw).into())
+}
+
+#[wasm_bindgen(js_name = "rv2coe")]
+pub fn js_rv2coe(position: &EciVec3, velocity: &EciVec3, gravity: GravityModel) -> JsValue {
+    to_value(&rv2coe(position, velocity, gravity)).unwrap()
+}
+
+#[wasm_bindgen(js_name = "coe2rv")]
+pub fn js_coe2rv(elements: JsValue, gravity: GravityModel) -> Result<JsValue, JsValue> {
+    let elements: ClassicalElements = from_value(elements)?;
+    Ok(to_value(&coe2rv(&elements, gravity)).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEG2RAD;
+    use crate::vector::sub;
+
+    fn assert_round_trip(elements: &ClassicalElements) {
+        let state = coe2rv(elements, GravityModel::Wgs84);
+        let back = rv2coe(state.position(), state.velocity(), GravityModel::Wgs84);
+        let again = coe2rv(&back, GravityModel::Wgs84);
+        let dr = sub(&state.position().into(), &again.position().into());
+        let dv = sub(&state.velocity().into(), &again.velocity().into());
+        assert!(norm(&dr) < 1e-6 && norm(&dv) < 1e-9);
+        assert!((back.p - elements.p).abs() < 1e-6);
+        assert!((back.ecc - elements.ecc).abs() < 1e-9);
+        assert!((back.incl - elements.incl).abs() < 1e-9);
+    }
+
+    fn elements(p: f64, ecc: f64, incl: f64) -> ClassicalElements {
+        ClassicalElements {
+            p,
+            a: f64::NAN,
+            ecc,
+            incl,
+            raan: 0.4,
+            argp: 1.1,
+            nu: 2.5,
+            m: f64::NAN,
+            arglat: 0.7,
+            truelon: 5.9,
+            lonper: 3.3,
+        }
+    }
+
+    #[test]
+    fn test_rv2coe_vallado_example() {
+        // Vallado, Fundamentals of Astrodynamics and Applications, example 2-5
+        let elements = rv2coe(
+            &EciVec3::new(6524.834, 6862.875, 6448.296),
+            &EciVec3::new(4.901327, 5.533756, -1.976341),
+            GravityModel::Wgs84,
+        );
+        assert!((elements.p - 11067.790).abs() < 0.01);
+        assert!((elements.a - 36127.343).abs() < 0.1);
+        assert!((elements.ecc - 0.832853).abs() < 1e-5);
+        assert!((elements.incl / DEG2RAD - 87.870).abs() < 1e-3);
+        assert!((elements.raan / DEG2RAD - 227.898).abs() < 1e-3);
+        assert!((elements.argp / DEG2RAD - 53.38).abs() < 1e-2);
+        assert!((elements.nu / DEG2RAD - 92.335).abs() < 1e-3);
+        assert!(elements.arglat.is_nan() && elements.truelon.is_nan());
+    }
+
+    #[test]
+    fn test_special_orbits() {
+        // elliptical inclined, circular inclined, elliptical equatorial,
+        // circular equatorial, retrograde equatorial and hyperbolic
+        assert_round_trip(&elements(7000.0, 0.1, 0.9));
+        assert_round_trip(&elements(7000.0, 0.0, 0.9));
+        assert_round_trip(&elements(7000.0, 0.1, 0.0));
+        assert_round_trip(&elements(42164.0, 0.0, 0.0));
+        assert_round_trip(&elements(7000.0, 0.2, PI));
+        let hyperbolic = ClassicalElements {
+            nu: 1.5,
+            ..elements(20000.0, 1.5, 0.5)
+        };
+        assert_round_trip(&hyperbolic);
+
+        let circular = coe2rv(&elements(7000.0, 0.0, 0.9), GravityModel::Wgs84);
+        let back = rv2coe(
+            circular.position(),
+            circular.velocity(),
+            GravityModel::Wgs84,
+        );
+        assert!(back.argp.is_nan() && back.nu.is_nan());
+        assert!((back.arglat - 0.7).abs() < 1e-9);
+        assert!((back.raan - 0.4).abs() < 1e-9);
+
+        let equatorial = coe2rv(&elements(42164.0, 0.0, 0.0), GravityModel::Wgs84);
+        let back = rv2coe(
+            equatorial.position(),
+            equatorial.velocity(),
+            GravityModel::Wgs84,
+        );
+        assert!(back.raan.is_nan() && back.lonper.is_nan());
+        assert!((back.truelon - 5.9).abs() < 1e-9);
+        assert!((back.a - 42164.0).abs() < 1e-6);
+
+        let state = coe2rv(&hyperbolic, GravityModel::Wgs84);
+        let back = rv2coe(state.position(), state.velocity(), GravityModel::Wgs84);
+        assert!(back.a < 0.0 && back.m > 0.0);
+        assert!((back.nu - 1.5).abs() < 1e-9);
+    }
+}
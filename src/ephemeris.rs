@@ -0,0 +1,439 @@
+use crate::propagator::{check_range, Propagator, PropagatorError, StateVector};
+use crate::transforms::{state_from_teme, state_to_teme, Frame};
+use crate::vector::{norm, sub, Vec3};
+use crate::{EciVec3, SatRec};
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Interpolation scheme of an [`Ephemeris`].
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Positions and velocities interpolated separately by a polynomial
+    /// through `order` samples.
+    Lagrange,
+    /// A polynomial matching positions and velocities at `order` samples,
+    /// twice the degree of Lagrange for the same window; the velocity is its
+    /// derivative. It needs velocities that are the exact derivative of the
+    /// positions: those of SGP4 are not, which limits Hermite to about a
+    /// metre on SGP4 samples, where Lagrange does better.
+    Hermite,
+}
+
+/// A table of states sampled at increasing Julian dates, interpolated at
+/// arbitrary dates between them.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct Ephemeris {
+    frame: Frame,
+    interpolation: Interpolation,
+    order: usize,
+    times: Vec<f64>,
+    positions: Vec<Vec3>,
+    velocities: Vec<Vec3>,
+    error_bound: Option<f64>,
+}
+
+impl Ephemeris {
+    /// Ephemeris through the `positions` (km) and `velocities` (km/s) at
+    /// increasing Julian dates `times`, given in `frame`. `order` is the
+    /// number of samples in each interpolation window, clamped to 2 and the
+    /// length of the table. Fails with [`PropagatorError::InvalidTable`] if
+    /// the table has fewer than two samples, the three vectors differ in
+    /// length or the dates do not strictly increase.
+    pub fn new(
+        times: Vec<f64>,
+        positions: Vec<Vec3>,
+        velocities: Vec<Vec3>,
+        frame: Frame,
+        interpolation: Interpolation,
+        order: usize,
+    ) -> Result<Ephemeris, PropagatorError> {
+        if times.len() < 2
+            || times.len() != positions.len()
+            || times.len() != velocities.len()
+            || times.windows(2).any(|pair| pair[1] <= pair[0])
+        {
+            return Err(PropagatorError::InvalidTable);
+        }
+        Ok(Ephemeris {
+            frame,
+            interpolation,
+            order: order.clamp(2, times.len()),
+            times,
+            positions,
+            velocities,
+            error_bound: None,
+        })
+    }
+
+    /// Sample `propagator` every `step` seconds from Julian date `start` to
+    /// `end`. The interpolated positions are then checked against the
+    /// propagator at the midpoint of every interval, and the largest
+    /// difference is kept as the [`error_bound`](Ephemeris::error_bound).
+    /// The window must not be empty and the step must be positive.
+    pub fn sample<P: Propagator + ?Sized>(
+        propagator: &mut P,
+        start: f64,
+        end: f64,
+        step: f64,
+        interpolation: Interpolation,
+        order: usize,
+    ) -> Result<Ephemeris, PropagatorError> {
+        if step <= 0.0 || end <= start {
+            return Err(PropagatorError::InvalidWindow);
+        }
+        check_range(propagator, start, end)?;
+        let n = ((end - start) * 86400.0 / step).ceil().max(1.0) as usize;
+        let mut times = Vec::with_capacity(n + 1);
+        let mut positions = Vec::with_capacity(n + 1);
+        let mut velocities = Vec::with_capacity(n + 1);
+        for i in 0..=n {
+            let t = start + (end - start) * (i as f64) / (n as f64);
+            let state = propagator.state_at(t)?;
+            times.push(t);
+            positions.push(state.position().into());
+            velocities.push(state.velocity().into());
+        }
+
+        let mut ephemeris = Ephemeris::new(
+            times,
+            positions,
+            velocities,
+            propagator.frame(),
+            interpolation,
+            order,
+        )?;
+        let mut error_bound: f64 = 0.0;
+        for window in ephemeris.times.windows(2) {
+            let t = 0.5 * (window[0] + window[1]);
+            let truth: Vec3 = propagator.state_at(t)?.position().into();
+            let (position, _) = ephemeris.interpolate(t);
+            error_bound = error_bound.max(norm(&sub(&position, &truth)));
+        }
+        ephemeris.error_bound = Some(error_bound);
+        Ok(ephemeris)
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Number of samples in each interpolation window.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn times(&self) -> &[f64] {
+        &self.times
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn velocities(&self) -> &[Vec3] {
+        &self.velocities
+    }
+
+    /// The table with its states converted to `frame`. The error bound is
+    /// kept, since the conversions do not change position differences.
+    pub(crate) fn to_frame(&self, frame: Frame) -> Ephemeris {
+        let mut converted = self.clone();
+        for (i, &t) in self.times.iter().enumerate() {
+            let (r, v) = state_to_teme(self.frame, t, &self.positions[i], &self.velocities[i]);
+            (converted.positions[i], converted.velocities[i]) = state_from_teme(frame, t, &r, &v);
+        }
+        converted.frame = frame;
+        converted
+    }
+
+    // First sample of the interpolation window around `jd`.
+    fn window(&self, jd: f64) -> usize {
+        let after = self.times.partition_point(|&t| t <= jd);
+        let first = after.saturating_sub(self.order / 2);
+        first.min(self.times.len() - self.order)
+    }
+
+    /// Position (km) and velocity (km/s) at Julian date `jd`. Dates outside
+    /// the table are extrapolated from the first or last window, which
+    /// [`state_at`](Propagator::state_at) refuses to do.
+    pub fn interpolate(&self, jd: f64) -> (Vec3, Vec3) {
+        let first = self.window(jd);
+        let last = first + self.order;
+        let t0 = self.times[first];
+        // seconds from the start of the window keep the polynomials well
+        // conditioned
+        let nodes: Vec<f64> = self.times[first..last]
+            .iter()
+            .map(|&t| (t - t0) * 86400.0)
+            .collect();
+        let x = (jd - t0) * 86400.0;
+        match self.interpolation {
+            Interpolation::Lagrange => (
+                lagrange(&nodes, &self.positions[first..last], x),
+                lagrange(&nodes, &self.velocities[first..last], x),
+            ),
+            Interpolation::Hermite => hermite(
+                &nodes,
+                &self.positions[first..last],
+                &self.velocities[first..last],
+                x,
+            ),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Ephemeris {
+    /// Largest position error of the interpolation at the midpoints between
+    /// samples, km, when the ephemeris was sampled from a propagator.
+    #[wasm_bindgen(getter, js_name = "errorBound")]
+    pub fn error_bound(&self) -> Option<f64> {
+        self.error_bound
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> f64 {
+        self.times[0]
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn end(&self) -> f64 {
+        self.times[self.times.len() - 1]
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn frame(&self) -> Frame {
+        self.frame
+    }
+
+    /// Interpolated position at Julian date `jd`, km.
+    #[wasm_bindgen(js_name = "positionAt")]
+    pub fn position_at(&mut self, jd: f64) -> Result<EciVec3, JsValue> {
+        match self.state_at(jd) {
+            Ok(state) => Ok(*state.position()),
+            Err(error) => Err(to_value(&error).unwrap()),
+        }
+    }
+
+    #[wasm_bindgen(js_name = "stateAt")]
+    pub fn js_state_at(&mut self, jd: f64) -> Result<JsValue, JsValue> {
+        match self.state_at(jd) {
+            Ok(state) => Ok(to_value(&state).unwrap()),
+            Err(error) => Err(to_value(&error).unwrap()),
+        }
+    }
+}
+
+impl Propagator for Ephemeris {
+    fn state_at(&mut self, jd: f64) -> Result<StateVector, PropagatorError> {
+        if jd < self.start() || jd > self.end() {
+            return Err(PropagatorError::OutOfRange);
+        }
+        let (position, velocity) = self.interpolate(jd);
+        Ok(StateVector::new(position.into(), velocity.into()))
+    }
+
+    fn valid_range(&self) -> (f64, f64) {
+        (self.start(), self.end())
+    }
+
+    fn frame(&self) -> Frame {
+        self.frame
+    }
+}
+
+// Value at `x` of the polynomial through `values` at `nodes`.
+fn lagrange(nodes: &[f64], values: &[Vec3], x: f64) -> Vec3 {
+    let mut result = [0.0; 3];
+    for (i, value) in values.iter().enumerate() {
+        let mut weight = 1.0;
+        for (j, node) in nodes.iter().enumerate() {
+            if j != i {
+                weight *= (x - node) / (nodes[i] - node);
+            }
+        }
+        for k in 0..3 {
+            result[k] += weight * value[k];
+        }
+    }
+    result
+}
+
+// Value and derivative at `x` of the polynomial matching `values` and
+// `derivatives` at `nodes`, from Newton divided differences over the doubled
+// nodes.
+fn hermite(nodes: &[f64], values: &[Vec3], derivatives: &[Vec3], x: f64) -> (Vec3, Vec3) {
+    let m = 2 * nodes.len();
+    let z: Vec<f64> = (0..m).map(|i| nodes[i / 2]).collect();
+    let mut value = [0.0; 3];
+    let mut derivative = [0.0; 3];
+    for k in 0..3 {
+        // coefficients[i] ends as f[z0, ..., zi]
+        let mut coefficients: Vec<f64> = (0..m).map(|i| values[i / 2][k]).collect();
+        for order in 1..m {
+            for i in (order..m).rev() {
+                coefficients[i] = if z[i] == z[i - order] {
+                    derivatives[i / 2][k]
+                } else {
+                    (coefficients[i] - coefficients[i - 1]) / (z[i] - z[i - order])
+                };
+            }
+        }
+        // Horner's scheme for the Newton form and its derivative
+        let (mut p, mut dp) = (coefficients[m - 1], 0.0);
+        for i in (0..m - 1).rev() {
+            dp = dp * (x - z[i]) + p;
+            p = p * (x - z[i]) + coefficients[i];
+        }
+        value[k] = p;
+        derivative[k] = dp;
+    }
+    (value, derivative)
+}
+
+#[wasm_bindgen(js_name = "sampleEphemeris")]
+pub fn js_sample_ephemeris(
+    satrec: &mut SatRec,
+    start: f64,
+    end: f64,
+    step: f64,
+    interpolation: Interpolation,
+    order: usize,
+) -> Result<Ephemeris, JsValue> {
+    Ephemeris::sample(satrec, start, end, step, interpolation, order)
+        .map_err(|error| to_value(&error).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::twoline2satrec;
+    use crate::KeplerPropagator;
+
+    fn iss() -> SatRec {
+        twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        )
+    }
+
+    #[test]
+    fn test_polynomials_are_exact() {
+        // a cubic is reproduced by four Lagrange points or two Hermite ones
+        let f = |x: f64| [x * x * x - 2.0 * x, 1.0, -x * x];
+        let df = |x: f64| [3.0 * x * x - 2.0, 0.0, -2.0 * x];
+        let nodes = [0.0, 1.0, 2.5, 4.0];
+        let values: Vec<Vec3> = nodes.iter().map(|&x| f(x)).collect();
+        let derivatives: Vec<Vec3> = nodes.iter().map(|&x| df(x)).collect();
+
+        let x = 1.7;
+        let value = lagrange(&nodes, &values, x);
+        assert!(norm(&sub(&value, &f(x))) < 1e-12);
+        let (value, derivative) = hermite(&nodes[1..3], &values[1..3], &derivatives[1..3], x);
+        assert!(norm(&sub(&value, &f(x))) < 1e-12);
+        assert!(norm(&sub(&derivative, &df(x))) < 1e-12);
+    }
+
+    fn check_sampled<P: Propagator>(
+        propagator: &mut P,
+        start: f64,
+        interpolation: Interpolation,
+        order: usize,
+        max_bound: f64,
+    ) {
+        let end = start + 0.1;
+        let mut ephemeris =
+            Ephemeris::sample(propagator, start, end, 120.0, interpolation, order).unwrap();
+        let bound = ephemeris.error_bound().unwrap();
+        assert!(bound < max_bound);
+        assert_eq!(ephemeris.valid_range(), (start, end));
+
+        for i in 0..50 {
+            let jd = start + 0.1 * (i as f64 + 0.37) / 50.0;
+            let interpolated = ephemeris.state_at(jd).unwrap();
+            let truth = propagator.state_at(jd).unwrap();
+            let dr = sub(&interpolated.position().into(), &truth.position().into());
+            let dv = sub(&interpolated.velocity().into(), &truth.velocity().into());
+            assert!(norm(&dr) <= 2.0 * bound + 1e-9);
+            assert!(norm(&dv) < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_sampled_ephemeris() {
+        let mut satrec = iss();
+        let start = satrec.jdsatepoch;
+        check_sampled(&mut satrec, start, Interpolation::Lagrange, 9, 1e-5);
+
+        let mut kepler = KeplerPropagator::from_satrec(&mut satrec, start).unwrap();
+        check_sampled(&mut kepler, start, Interpolation::Hermite, 4, 1e-6);
+        check_sampled(&mut kepler, start, Interpolation::Lagrange, 9, 1e-5);
+    }
+
+    #[test]
+    fn test_table_ends() {
+        let mut satrec = iss();
+        let start = satrec.jdsatepoch;
+        let ephemeris = Ephemeris::sample(
+            &mut satrec,
+            start,
+            start + 0.02,
+            60.0,
+            Interpolation::Lagrange,
+            50,
+        )
+        .unwrap();
+        // the order is clamped to the length of the table
+        assert_eq!(ephemeris.order, ephemeris.times().len());
+        let (position, _) = ephemeris.interpolate(start);
+        assert!(norm(&sub(&position, &ephemeris.positions()[0])) < 1e-9);
+        let (position, _) = ephemeris.interpolate(ephemeris.end());
+        assert!(norm(&sub(&position, ephemeris.positions().last().unwrap())) < 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_tables_and_windows() {
+        let mut satrec = iss();
+        let start = satrec.jdsatepoch;
+        let mut ephemeris = Ephemeris::sample(
+            &mut satrec,
+            start,
+            start + 0.01,
+            60.0,
+            Interpolation::Lagrange,
+            8,
+        )
+        .unwrap();
+        assert!(ephemeris.state_at(ephemeris.end()).is_ok());
+        for jd in [start - 1e-6, ephemeris.end() + 1e-6] {
+            let error = ephemeris.state_at(jd).err();
+            assert_eq!(error, Some(PropagatorError::OutOfRange));
+        }
+
+        for (end, step) in [(start + 0.01, 0.0), (start + 0.01, -60.0), (start, 60.0)] {
+            let error =
+                Ephemeris::sample(&mut satrec, start, end, step, Interpolation::Lagrange, 8).err();
+            assert_eq!(error, Some(PropagatorError::InvalidWindow));
+        }
+
+        let invalid = |times: Vec<f64>, length: usize| {
+            let table = Ephemeris::new(
+                times,
+                vec![[7000.0, 0.0, 0.0]; length],
+                vec![[0.0, 7.5, 0.0]; length],
+                Frame::Teme,
+                Interpolation::Lagrange,
+                8,
+            );
+            matches!(table, Err(PropagatorError::InvalidTable))
+        };
+        assert!(!invalid(vec![start, start + 0.01], 2));
+        assert!(invalid(vec![start], 1));
+        assert!(invalid(vec![start, start + 0.01], 3));
+        assert!(invalid(vec![start, start, start + 0.01], 3));
+        assert!(invalid(vec![start + 0.01, start], 2));
+    }
+}
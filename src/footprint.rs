@@ -0,0 +1,231 @@
+use crate::constants::{PI, RAD2DEG, TWO_PI};
+use crate::search::find_root;
+use crate::transforms::{ecf_to_geodetic, ecf_to_look_angles, A, B};
+use crate::vector::{dot, Vec3};
+use crate::{EcfVec3, GeodeticLocation};
+use serde::Serialize;
+use serde_json::{json, Value};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::prelude::*;
+
+/// Tolerance on the Earth central angle, radians (about 6 mm).
+const ANGLE_TOLERANCE: f64 = 1e-9;
+
+/// Why a footprint could not be computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum FootprintError {
+    /// The satellite is not above the ellipsoid.
+    BelowSurface,
+    /// The minimum elevation is not below 90°, so no ground point sees the
+    /// satellite that high.
+    InvalidElevation,
+}
+
+// Point on the ellipsoid at central angle `distance` and initial bearing
+// `azimuth` from `origin`, both radians, on the sphere through its latitude.
+fn destination(origin: &GeodeticLocation, azimuth: f64, distance: f64) -> GeodeticLocation {
+    let (sin_lat, cos_lat) = origin.latitude.sin_cos();
+    let latitude = (sin_lat * distance.cos() + cos_lat * distance.sin() * azimuth.cos()).asin();
+    let longitude = origin.longitude
+        + (azimuth.sin() * distance.sin() * cos_lat)
+            .atan2(distance.cos() - sin_lat * latitude.sin());
+    GeodeticLocation::new(longitude, latitude, 0.0)
+}
+
+/// Ground coverage circle of a satellite at Earth-fixed position `satellite`
+/// (km): the `points` locations, evenly spaced in azimuth around the
+/// sub-satellite point, from which the satellite is seen at `min_elevation`
+/// (radians).
+///
+/// Elevations are evaluated on the WGS84 ellipsoid. Longitudes follow the
+/// outline continuously and may leave the -pi .. pi range.
+pub fn coverage_footprint(
+    satellite: &EcfVec3,
+    min_elevation: f64,
+    points: usize,
+) -> Result<Vec<GeodeticLocation>, FootprintError> {
+    let nadir = ecf_to_geodetic(satellite);
+    if nadir.height <= 0.0 {
+        return Err(FootprintError::BelowSurface);
+    }
+    if min_elevation >= 0.5 * PI {
+        return Err(FootprintError::InvalidElevation);
+    }
+    let elevation_at = |azimuth: f64, distance: f64| -> Result<f64, FootprintError> {
+        let ground = destination(&nadir, azimuth, distance);
+        Ok(ecf_to_look_angles(&ground, satellite).elevation() - min_elevation)
+    };
+
+    (0..points)
+        .map(|k| {
+            let azimuth = TWO_PI * (k as f64) / (points as f64);
+            let distance = find_root(|d| elevation_at(azimuth, d), 0.0, 0.5 * PI, ANGLE_TOLERANCE)?;
+            Ok(destination(&nadir, azimuth, distance))
+        })
+        .collect()
+}
+
+/// Footprint of a nadir-pointing cone sensor with half-angle `half_angle`
+/// (radians) on a satellite at Earth-fixed position `satellite` (km).
+///
+/// The `points` edge rays are intersected exactly with the WGS84 ellipsoid.
+/// Rays that miss the Earth are replaced by the horizon point in the same
+/// direction. Longitudes follow the outline continuously.
+pub fn sensor_footprint(
+    satellite: &EcfVec3,
+    half_angle: f64,
+    points: usize,
+) -> Result<Vec<GeodeticLocation>, FootprintError> {
+    let nadir = ecf_to_geodetic(satellite);
+    let (sin_lat, cos_lat) = nadir.latitude.sin_cos();
+    let (sin_lon, cos_lon) = nadir.longitude.sin_cos();
+    let down = [-cos_lat * cos_lon, -cos_lat * sin_lon, -sin_lat];
+    let north = [-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat];
+    let east = [-sin_lon, cos_lon, 0.0];
+
+    // the ellipsoid becomes the unit sphere once z is stretched by a / b
+    let position = [satellite.x / A, satellite.y / A, satellite.z / B];
+    let horizon = coverage_footprint(satellite, 0.0, points)?;
+
+    Ok((0..points)
+        .map(|k| {
+            let azimuth = TWO_PI * (k as f64) / (points as f64);
+            let (sin_h, cos_h) = half_angle.sin_cos();
+            let (sin_a, cos_a) = azimuth.sin_cos();
+            let ray: Vec3 =
+                [0, 1, 2].map(|i| cos_h * down[i] + sin_h * (cos_a * north[i] + sin_a * east[i]));
+            let direction = [ray[0] / A, ray[1] / A, ray[2] / B];
+
+            let a = dot(&direction, &direction);
+            let b = 2.0 * dot(&position, &direction);
+            let c = dot(&position, &position) - 1.0;
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return horizon[k];
+            }
+            let t = (-b - discriminant.sqrt()) / (2.0 * a);
+            let hit = EcfVec3::new(
+                satellite.x + t * ray[0],
+                satellite.y + t * ray[1],
+                satellite.z + t * ray[2],
+            );
+            let mut location = ecf_to_geodetic(&hit);
+            location.height = 0.0;
+            // keep the outline continuous around the sub-satellite point
+            while location.longitude - nadir.longitude > PI {
+                location.longitude -= TWO_PI;
+            }
+            while location.longitude - nadir.longitude < -PI {
+                location.longitude += TWO_PI;
+            }
+            location
+        })
+        .collect())
+}
+
+/// A footprint outline as a closed GeoJSON Polygon, coordinates in degrees.
+pub fn footprint_geojson(footprint: &[GeodeticLocation]) -> Value {
+    let mut ring: Vec<[f64; 2]> = footprint
+        .iter()
+        .map(|location| [location.longitude * RAD2DEG, location.latitude * RAD2DEG])
+        .collect();
+    if let Some(&first) = ring.first() {
+        ring.push(first);
+    }
+    json!({
+        "type": "Polygon",
+        "coordinates": [ring],
+    })
+}
+
+#[wasm_bindgen(js_name = "coverageFootprint")]
+pub fn js_coverage_footprint(
+    satellite: &EcfVec3,
+    min_elevation: f64,
+    points: usize,
+) -> Result<JsValue, JsValue> {
+    match coverage_footprint(satellite, min_elevation, points) {
+        Ok(footprint) => Ok(to_value(&footprint).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[wasm_bindgen(js_name = "sensorFootprint")]
+pub fn js_sensor_footprint(
+    satellite: &EcfVec3,
+    half_angle: f64,
+    points: usize,
+) -> Result<JsValue, JsValue> {
+    match sensor_footprint(satellite, half_angle, points) {
+        Ok(footprint) => Ok(to_value(&footprint).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+/// The GeoJSON Polygon of [`footprint_geojson`] as a JSON string, from a
+/// footprint returned by `coverageFootprint` or `sensorFootprint`.
+#[wasm_bindgen(js_name = "footprintGeoJson")]
+pub fn js_footprint_geojson(footprint: JsValue) -> Result<String, JsValue> {
+    let footprint: Vec<GeodeticLocation> = from_value(footprint)?;
+    Ok(footprint_geojson(&footprint).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEG2RAD;
+    use crate::transforms::geodetic_to_ecf;
+
+    #[test]
+    fn test_coverage_footprint() {
+        let satellite = geodetic_to_ecf(&GeodeticLocation::from_degrees(20.0, 40.0, 500.0));
+        let min_elevation = 10.0 * DEG2RAD;
+
+        let footprint = coverage_footprint(&satellite, min_elevation, 36).unwrap();
+
+        assert_eq!(footprint.len(), 36);
+        for location in &footprint {
+            let elevation = ecf_to_look_angles(location, &satellite).elevation();
+            assert!((elevation - min_elevation).abs() < 1e-6);
+        }
+
+        assert_eq!(
+            coverage_footprint(&satellite, 90.0 * DEG2RAD, 36).unwrap_err(),
+            FootprintError::InvalidElevation
+        );
+        let buried = geodetic_to_ecf(&GeodeticLocation::from_degrees(20.0, 40.0, -1.0));
+        assert_eq!(
+            sensor_footprint(&buried, 10.0 * DEG2RAD, 12).unwrap_err(),
+            FootprintError::BelowSurface
+        );
+    }
+
+    #[test]
+    fn test_sensor_footprint() {
+        let satellite = geodetic_to_ecf(&GeodeticLocation::from_degrees(20.0, 40.0, 500.0));
+        let nadir = geodetic_to_ecf(&GeodeticLocation::from_degrees(20.0, 40.0, 0.0));
+
+        // a narrow cone hits the ground about h * tan(half_angle) from nadir
+        let footprint = sensor_footprint(&satellite, 10.0 * DEG2RAD, 12).unwrap();
+        for location in &footprint {
+            let ground = geodetic_to_ecf(location);
+            let look = ecf_to_look_angles(location, &satellite);
+            let radius = ((ground.x - nadir.x).powi(2)
+                + (ground.y - nadir.y).powi(2)
+                + (ground.z - nadir.z).powi(2))
+            .sqrt();
+            assert!((radius - 500.0 * (10.0 * DEG2RAD).tan()).abs() < 2.0);
+            assert!(location.height.abs() < 1e-9);
+            assert!(look.elevation() > 79.0 * DEG2RAD);
+        }
+
+        // a cone wider than the Earth is clipped to the horizon
+        let wide = sensor_footprint(&satellite, 80.0 * DEG2RAD, 12).unwrap();
+        for location in &wide {
+            assert!(ecf_to_look_angles(location, &satellite).elevation().abs() < 1e-6);
+        }
+
+        let polygon = footprint_geojson(&footprint);
+        assert_eq!(polygon["coordinates"][0].as_array().unwrap().len(), 13);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::constants::{MINUTES_PER_DAY, PI, RAD2DEG, TWO_PI};
+use crate::propagation::gstime::gstime;
+use crate::propagation::sgp4::sgp4;
+use crate::propagator::PropagatorError;
+use crate::transforms::eci_to_geodetic;
+use crate::SatRec;
+use serde::Serialize;
+use serde_json::{json, Value};
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Sub-satellite point. Angles are in radians, height in km.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct GroundTrackPoint {
+    /// Julian date.
+    pub jd: f64,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub height: f64,
+}
+
+/// Sub-satellite points split into segments that do not cross the
+/// antimeridian. Segments that touch it end or start exactly at ±180°.
+#[derive(Clone, Debug, Serialize)]
+pub struct GroundTrack {
+    pub segments: Vec<Vec<GroundTrackPoint>>,
+}
+
+impl GroundTrack {
+    /// The track as a GeoJSON FeatureCollection with one LineString per
+    /// segment, coordinates in degrees.
+    pub fn to_geojson(&self) -> Value {
+        let features: Vec<Value> = self
+            .segments
+            .iter()
+            .map(|segment| {
+                let coordinates: Vec<[f64; 2]> = segment
+                    .iter()
+                    .map(|point| [point.longitude * RAD2DEG, point.latitude * RAD2DEG])
+                    .collect();
+                json!({
+                    "type": "Feature",
+                    "properties": {
+                        "start": segment.first().map(|point| point.jd),
+                        "end": segment.last().map(|point| point.jd),
+                    },
+                    "geometry": {
+                        "type": "LineString",
+                        "coordinates": coordinates,
+                    },
+                })
+            })
+            .collect();
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    }
+}
+
+// Point where the track from `a` to `b` meets the antimeridian, with the
+// longitude on the side of `a`.
+fn antimeridian_crossing(a: &GroundTrackPoint, b: &GroundTrackPoint) -> GroundTrackPoint {
+    let side = if a.longitude > 0.0 { PI } else { -PI };
+    let unwrapped = b.longitude + if a.longitude > 0.0 { TWO_PI } else { -TWO_PI };
+    let f = (side - a.longitude) / (unwrapped - a.longitude);
+    GroundTrackPoint {
+        jd: a.jd + f * (b.jd - a.jd),
+        latitude: a.latitude + f * (b.latitude - a.latitude),
+        longitude: side,
+        height: a.height + f * (b.height - a.height),
+    }
+}
+
+/// Ground track of `satrec` sampled every `step` seconds from `start` to
+/// `end` (Julian dates). Returns [`PropagatorError::InvalidWindow`] unless
+/// `step` is positive and `end` is not before `start`.
+pub fn ground_track(
+    satrec: &mut SatRec,
+    start: f64,
+    end: f64,
+    step: f64,
+) -> Result<GroundTrack, PropagatorError> {
+    if step <= 0.0 || end < start {
+        return Err(PropagatorError::InvalidWindow);
+    }
+    let n = ((end - start) * 86400.0 / step).floor().max(0.0) as usize;
+    let mut segments = Vec::new();
+    let mut segment: Vec<GroundTrackPoint> = Vec::new();
+
+    for i in 0..=n {
+        let jd = start + (step * i as f64) / 86400.0;
+        let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+        let geodetic = eci_to_geodetic(result.position(), gstime(jd));
+        let point = GroundTrackPoint {
+            jd,
+            latitude: geodetic.latitude,
+            longitude: geodetic.longitude,
+            height: geodetic.height,
+        };
+
+        if let Some(last) = segment.last() {
+            if (point.longitude - last.longitude).abs() > PI {
+                let crossing = antimeridian_crossing(last, &point);
+                segment.push(crossing);
+                segments.push(segment);
+                segment = vec![GroundTrackPoint {
+                    longitude: -crossing.longitude,
+                    ..crossing
+                }];
+            }
+        }
+        segment.push(point);
+    }
+    segments.push(segment);
+
+    Ok(GroundTrack { segments })
+}
+
+#[wasm_bindgen(js_name = "groundTrack")]
+pub fn js_ground_track(
+    satrec: &mut SatRec,
+    start: f64,
+    end: f64,
+    step: f64,
+) -> Result<JsValue, JsValue> {
+    match ground_track(satrec, start, end, step) {
+        Ok(track) => Ok(to_value(&track).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[wasm_bindgen(js_name = "groundTrackGeoJson")]
+pub fn js_ground_track_geojson(
+    satrec: &mut SatRec,
+    start: f64,
+    end: f64,
+    step: f64,
+) -> Result<String, JsValue> {
+    match ground_track(satrec, start, end, step) {
+        Ok(track) => Ok(track.to_geojson().to_string()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::jday;
+    use crate::io::twoline2satrec;
+
+    #[test]
+    fn test_ground_track_antimeridian() {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let start = jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+
+        // three orbits cross the antimeridian three times
+        let track = ground_track(&mut satrec, start, start + 0.2, 30.0).unwrap();
+        assert!(track.segments.len() >= 3 && track.segments.len() <= 4);
+
+        for (i, segment) in track.segments.iter().enumerate() {
+            for pair in segment.windows(2) {
+                assert!((pair[1].longitude - pair[0].longitude).abs() < 0.5);
+                assert!(pair[1].latitude.abs() <= 51.7f64.to_radians() + 0.01);
+            }
+            if i > 0 {
+                let previous = track.segments[i - 1].last().unwrap();
+                let first = segment.first().unwrap();
+                assert_eq!(previous.longitude, -first.longitude);
+                assert_eq!(previous.latitude, first.latitude);
+                assert_eq!(first.longitude.abs(), PI);
+            }
+        }
+
+        let geojson = track.to_geojson();
+        assert_eq!(
+            geojson["features"].as_array().unwrap().len(),
+            track.segments.len()
+        );
+        assert_eq!(geojson["features"][0]["geometry"]["type"], "LineString");
+
+        assert_eq!(
+            ground_track(&mut satrec, start, start + 0.2, -30.0).unwrap_err(),
+            PropagatorError::InvalidWindow
+        );
+        assert_eq!(
+            ground_track(&mut satrec, start, start - 0.2, 30.0).unwrap_err(),
+            PropagatorError::InvalidWindow
+        );
+    }
+}
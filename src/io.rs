@@ -6,6 +6,7 @@ use crate::constants::{DEG2RAD, PI};
 use crate::ext::{days2mdhms, jday};
 use crate::propagation::sgp4init::{sgp4init, Sgp4InitOptions};
 use crate::{DpperOpsMode, SatRec};
+use serde_wasm_bindgen::from_value;
 
 fn parse_float(str: &str) -> f64 {
     return str.parse::<f64>().unwrap();
@@ -175,3 +176,14 @@ pub fn twoline2satrec(longstr1: &str, longstr2: &str) -> SatRec {
 
     satrec
 }
+
+/// Element sets from an array of `[line1, line2]` pairs. JavaScript
+/// functions that need several element sets take them this way, since a
+/// `SatRec` passed by value is consumed and its handle left dead.
+pub(crate) fn satrecs_from_tles(tles: JsValue) -> Result<Vec<SatRec>, JsValue> {
+    let tles: Vec<[String; 2]> = from_value(tles)?;
+    Ok(tles
+        .iter()
+        .map(|[line1, line2]| twoline2satrec(line1, line2))
+        .collect())
+}
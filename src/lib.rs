@@ -7,39 +7,113 @@ use wasm_bindgen::prelude::*;
 pub mod constants;
 use serde::{Deserialize, Serialize};
 
+mod beta_angle;
+mod collision;
+mod conjunction;
+mod coverage;
+mod czml;
 mod doppler_factor;
+mod eclipse;
+mod elements;
+mod ephemeris;
 mod ext;
+mod footprint;
+mod ground_track;
 mod io;
+mod local_frames;
+mod magnitude;
+mod moon;
+mod numerical;
+mod oem;
+mod orbit_determination;
+mod passes;
 mod propagation;
+mod propagator;
+mod refraction;
+mod search;
+mod sun;
 mod transforms;
+mod vector;
+mod visibility;
+pub use elements::{coe2rv, rv2coe, ClassicalElements};
+pub use ephemeris::{Ephemeris, Interpolation};
 pub use ext::{jday, jday_date};
+pub use footprint::{coverage_footprint, footprint_geojson, sensor_footprint, FootprintError};
+pub use ground_track::{ground_track, GroundTrack, GroundTrackPoint};
 pub use io::twoline2satrec;
+pub use local_frames::{
+    inertial_to_local, local_to_inertial, ric_difference, LocalFrame, LocalState,
+};
+pub use magnitude::{estimate_magnitude, visual_magnitude};
+pub use moon::{moon_phase, moon_position_ecf, moon_position_j2000, moon_position_teme, MoonPhase};
+pub use numerical::{ForceModel, NumericalPropagator};
+pub use oem::{Oem, OemError, OemMetadata, OemSegment};
+pub use orbit_determination::{
+    fit_tle, re_epoch, FitError, FitOptions, FitStatistics, MeanElements, Observation, TleFit,
+};
+pub use passes::{find_passes, look_angles_at, Pass};
 pub use propagation::{
     gstime::gstime,
     propagate::{propagate, propagate_date},
     sgp4::{sgp4, Sgp4Error, Sgp4Result},
 };
+pub use propagator::{KeplerPropagator, Propagator, PropagatorError, StateVector};
 
-pub use doppler_factor::doppler_factor;
+pub use beta_angle::{beta_angle, beta_angle_history, eclipse_fraction, BetaAngleSample};
+pub use collision::{
+    collision_probability, collision_probability_2d, encounter_plane, CollisionError, PcMethod,
+};
+pub use conjunction::{screen_conjunctions, Conjunction, Screening};
+pub use coverage::{coverage_analysis, region_grid, GridError, PointCoverage};
+pub use czml::{czml_document, czml_packet, CzmlFrame, CzmlOptions};
+pub use doppler_factor::{doppler_curve, doppler_factor, DopplerSample};
+pub use eclipse::{eclipse, find_eclipses, Eclipse, EclipseInterval, EclipseState};
+pub use refraction::{ecf_to_look_angles_refracted, refraction, Atmosphere, RefractionModel};
+pub use sun::{sun_position_ecf, sun_position_j2000, sun_position_teme};
+pub use visibility::{find_visible_passes, Twilight, VisiblePass, VisibleSegment};
 
 pub use transforms::{
-    degrees_lat, degrees_long, degrees_to_radians, ecf_to_eci, ecf_to_look_angles, eci_to_ecf,
-    eci_to_geodetic, geodetic_to_ecf, radians_lat, radians_long, radians_to_degrees,
+    degrees_lat, degrees_long, degrees_to_radians, ecf_to_eci, ecf_to_geodetic, ecf_to_look_angles,
+    eci_to_ecf, eci_to_geodetic, geodetic_to_ecf, j2000_to_teme, radians_lat, radians_long,
+    radians_to_degrees, teme_to_j2000, topocentric, topocentric_to_look_angles, Frame,
 };
+/// Cartesian vector in an Earth-centred inertial frame (TEME for SGP4 output), km or km/s.
 #[wasm_bindgen]
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct EciVec3 {
     pub x: f64,
     pub y: f64,
     pub z: f64,
 }
+
+#[wasm_bindgen]
+impl EciVec3 {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f64, y: f64, z: f64) -> EciVec3 {
+        EciVec3 { x, y, z }
+    }
+}
+
+/// Cartesian vector in the Earth-centred, Earth-fixed frame, km or km/s.
 #[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct EcfVec3 {
     pub x: f64,
     pub y: f64,
     pub z: f64,
 }
+
+#[wasm_bindgen]
+impl EcfVec3 {
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: f64, y: f64, z: f64) -> EcfVec3 {
+        EcfVec3 { x, y, z }
+    }
+}
+
+/// Geodetic position on the WGS84 ellipsoid. Angles are in radians, height in km.
 #[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct GeodeticLocation {
     pub longitude: f64,
     pub latitude: f64,
@@ -47,19 +121,121 @@ pub struct GeodeticLocation {
 }
 
 #[wasm_bindgen]
+impl GeodeticLocation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(longitude: f64, latitude: f64, height: f64) -> GeodeticLocation {
+        GeodeticLocation {
+            longitude,
+            latitude,
+            height,
+        }
+    }
+
+    /// Build a location from longitude and latitude given in degrees.
+    #[wasm_bindgen(js_name = "fromDegrees")]
+    pub fn from_degrees(longitude: f64, latitude: f64, height: f64) -> GeodeticLocation {
+        GeodeticLocation {
+            longitude: longitude * constants::DEG2RAD,
+            latitude: latitude * constants::DEG2RAD,
+            height,
+        }
+    }
+
+    #[wasm_bindgen(getter, js_name = "longitudeDeg")]
+    pub fn longitude_deg(&self) -> f64 {
+        self.longitude * constants::RAD2DEG
+    }
+
+    #[wasm_bindgen(getter, js_name = "latitudeDeg")]
+    pub fn latitude_deg(&self) -> f64 {
+        self.latitude * constants::RAD2DEG
+    }
+}
+
+/// Observer-relative south, east and zenith components (SEZ frame), km.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Topocentric {
     top_s: f64,
     top_e: f64,
     top_z: f64,
 }
-#[allow(dead_code)]
+
 #[wasm_bindgen]
+impl Topocentric {
+    #[wasm_bindgen(constructor)]
+    pub fn new(top_s: f64, top_e: f64, top_z: f64) -> Topocentric {
+        Topocentric {
+            top_s,
+            top_e,
+            top_z,
+        }
+    }
+
+    #[wasm_bindgen(getter, js_name = "topS")]
+    pub fn top_s(&self) -> f64 {
+        self.top_s
+    }
+
+    #[wasm_bindgen(getter, js_name = "topE")]
+    pub fn top_e(&self) -> f64 {
+        self.top_e
+    }
+
+    #[wasm_bindgen(getter, js_name = "topZ")]
+    pub fn top_z(&self) -> f64 {
+        self.top_z
+    }
+}
+
+/// Azimuth (from north, through east) and elevation in radians, range in km.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct LookAngles {
     azimuth: f64,
     elevation: f64,
     range_sat: f64,
 }
 
+#[wasm_bindgen]
+impl LookAngles {
+    #[wasm_bindgen(constructor)]
+    pub fn new(azimuth: f64, elevation: f64, range_sat: f64) -> LookAngles {
+        LookAngles {
+            azimuth,
+            elevation,
+            range_sat,
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn azimuth(&self) -> f64 {
+        self.azimuth
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn elevation(&self) -> f64 {
+        self.elevation
+    }
+
+    #[wasm_bindgen(getter, js_name = "rangeSat")]
+    pub fn range_sat(&self) -> f64 {
+        self.range_sat
+    }
+
+    #[wasm_bindgen(getter, js_name = "azimuthDeg")]
+    pub fn azimuth_deg(&self) -> f64 {
+        self.azimuth * constants::RAD2DEG
+    }
+
+    #[wasm_bindgen(getter, js_name = "elevationDeg")]
+    pub fn elevation_deg(&self) -> f64 {
+        self.elevation * constants::RAD2DEG
+    }
+}
+
 /// Satellite record containing description of orbit.
 
 #[derive(Clone, Debug)]
@@ -185,6 +361,8 @@ pub struct SatRec {
     pub altp: f64,
     /// Error code indicating propagation failure type.
     pub error: u32,
+    /// Standard visual magnitude at 1000 km range and 90° phase angle, if known.
+    intrinsic_magnitude: Option<f64>,
 }
 
 #[wasm_bindgen]
@@ -294,6 +472,7 @@ impl SatRec {
             alta: 0.0,
             altp: 0.0,
             error: 0,
+            intrinsic_magnitude: None,
         }
     }
 
@@ -318,6 +497,107 @@ impl SatRec {
             DpperInit::N => 'n',
         }
     }
+
+    #[wasm_bindgen(getter, js_name = "intrinsicMagnitude")]
+    pub fn intrinsic_magnitude(&self) -> Option<f64> {
+        self.intrinsic_magnitude
+    }
+
+    #[wasm_bindgen(setter, js_name = "intrinsicMagnitude")]
+    pub fn set_intrinsic_magnitude(&mut self, magnitude: Option<f64>) {
+        self.intrinsic_magnitude = magnitude;
+    }
+
+    /// Orbital period in minutes, from the Brouwer mean motion recovered by
+    /// SGP4 initialisation.
+    #[wasm_bindgen(getter)]
+    pub fn period(&self) -> f64 {
+        constants::TWO_PI / self.no
+    }
+
+    /// Mean motion in revolutions per day. This is the Brouwer mean motion
+    /// used by SGP4, which differs slightly from the Kozai value in the TLE.
+    #[wasm_bindgen(getter, js_name = "meanMotionRevPerDay")]
+    pub fn mean_motion_rev_per_day(&self) -> f64 {
+        self.no * constants::MINUTES_PER_DAY / constants::TWO_PI
+    }
+
+    #[wasm_bindgen(getter, js_name = "inclinationDeg")]
+    pub fn inclination_deg(&self) -> f64 {
+        self.inclo * constants::RAD2DEG
+    }
+
+    /// Right ascension of the ascending node in degrees.
+    #[wasm_bindgen(getter, js_name = "raanDeg")]
+    pub fn raan_deg(&self) -> f64 {
+        self.nodeo * constants::RAD2DEG
+    }
+
+    /// Argument of perigee in degrees.
+    #[wasm_bindgen(getter, js_name = "argpDeg")]
+    pub fn argp_deg(&self) -> f64 {
+        self.argpo * constants::RAD2DEG
+    }
+
+    #[wasm_bindgen(getter, js_name = "meanAnomalyDeg")]
+    pub fn mean_anomaly_deg(&self) -> f64 {
+        self.mo * constants::RAD2DEG
+    }
+
+    /// Mean semi-major axis in km.
+    #[wasm_bindgen(getter, js_name = "semiMajorAxisKm")]
+    pub fn semi_major_axis_km(&self) -> f64 {
+        self.a * constants::EARTH_RADIUS
+    }
+
+    /// Mean apogee altitude above the equatorial radius, km.
+    #[wasm_bindgen(getter, js_name = "apogeeAltitudeKm")]
+    pub fn apogee_altitude_km(&self) -> f64 {
+        self.alta * constants::EARTH_RADIUS
+    }
+
+    /// Mean perigee altitude above the equatorial radius, km.
+    #[wasm_bindgen(getter, js_name = "perigeeAltitudeKm")]
+    pub fn perigee_altitude_km(&self) -> f64 {
+        self.altp * constants::EARTH_RADIUS
+    }
+
+    /// Whether SGP4 runs the deep-space (SDP4) branch for this element set,
+    /// which it does for periods of 225 minutes and longer.
+    #[wasm_bindgen(getter, js_name = "isDeepSpace")]
+    pub fn is_deep_space(&self) -> bool {
+        self.method == 'd'
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn regime(&self) -> OrbitRegime {
+        if self.apogee_altitude_km() < 2000.0 {
+            OrbitRegime::Leo
+        } else if self.ecco > 0.25 {
+            OrbitRegime::Heo
+        } else if (0.9..1.1).contains(&self.mean_motion_rev_per_day()) {
+            OrbitRegime::Geo
+        } else if self.mean_motion_rev_per_day() > 1.1 {
+            OrbitRegime::Meo
+        } else {
+            OrbitRegime::Heo
+        }
+    }
+}
+
+/// Broad orbit classification of an element set.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrbitRegime {
+    /// Low Earth orbit: apogee below 2000 km.
+    Leo,
+    /// Medium Earth orbit: near-circular, between LEO and the geosynchronous
+    /// belt.
+    Meo,
+    /// Near-circular orbit of 0.9 to 1.1 revolutions per day.
+    Geo,
+    /// Highly elliptical (eccentricity above 0.25) or beyond geosynchronous.
+    Heo,
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -356,6 +636,7 @@ pub fn get_constants() -> JsValue {
     map.insert("minutesPerDay", constants::MINUTES_PER_DAY);
     map.insert("mu", constants::MU);
     map.insert("earthRadius", constants::EARTH_RADIUS);
+    map.insert("au", constants::AU);
     map.insert("xke", constants::XKE);
     map.insert("vkmpersec", constants::VKMPERSEC);
     map.insert("tumin", constants::TUMIN);
@@ -369,3 +650,46 @@ pub fn get_constants() -> JsValue {
     let json_str = serde_json::to_string(&map).unwrap();
     JsValue::from_str(&json_str)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satrec_accessors() {
+        let iss = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        assert!((iss.mean_motion_rev_per_day() - 15.50377579).abs() < 0.02);
+        assert!((iss.period() * iss.mean_motion_rev_per_day() - 1440.0).abs() < 1e-9);
+        assert!((iss.inclination_deg() - 51.6416).abs() < 1e-9);
+        assert!((iss.raan_deg() - 247.4627).abs() < 1e-9);
+        assert!((iss.argp_deg() - 130.5360).abs() < 1e-9);
+        assert!((iss.mean_anomaly_deg() - 325.0288).abs() < 1e-9);
+        assert!((iss.semi_major_axis_km() - 6795.0).abs() < 5.0);
+        assert!(iss.perigee_altitude_km() < iss.apogee_altitude_km());
+        assert!((iss.apogee_altitude_km() - iss.perigee_altitude_km() - 9.1).abs() < 0.1);
+        assert_eq!(iss.regime(), OrbitRegime::Leo);
+        assert!(!iss.is_deep_space());
+
+        let molniya = twoline2satrec(
+            "1 07276U 74026A   24001.50000000  .00000100  00000-0  00000-0 0  9997",
+            "2 07276  62.8000 280.0000 7200000 270.0000  10.0000  2.00600000300000",
+        );
+        assert_eq!(molniya.regime(), OrbitRegime::Heo);
+        assert!(molniya.is_deep_space());
+
+        let gps = twoline2satrec(
+            "1 24876U 97035A   24001.50000000  .00000000  00000-0  00000-0 0  9990",
+            "2 24876  55.5000 100.0000 0050000  50.0000 310.0000  2.00560000190000",
+        );
+        assert_eq!(gps.regime(), OrbitRegime::Meo);
+
+        let geo = twoline2satrec(
+            "1 28884U 05041A   24001.50000000  .00000000  00000-0  00000-0 0  9995",
+            "2 28884   0.0500  90.0000 0002000 100.0000 200.0000  1.00270000 67000",
+        );
+        assert_eq!(geo.regime(), OrbitRegime::Geo);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::propagation::sgp4::Sgp4Result;
+use crate::propagator::StateVector;
+use crate::vector::{add, cross, dot, mat_vec, scale, sub, transpose, unit, Mat3, Vec3};
+use crate::EciVec3;
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::prelude::*;
+
+/// Local orbital frames attached to a reference state.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LocalFrame {
+    /// Radial, in-track, cross-track (also RSW): R along the position, C
+    /// along the orbit normal and I completing the triad, close to the
+    /// velocity.
+    Ric,
+    /// Normal, tangential, cross-track: T along the velocity, W along the
+    /// orbit normal and N completing the triad, pointing out of the orbit.
+    Ntw,
+    /// Local vertical, local horizontal as defined by CCSDS: Z towards the
+    /// centre of the Earth, Y against the orbit normal and X completing the
+    /// triad, close to the velocity.
+    Lvlh,
+}
+
+/// Position (km) and velocity (km/s) of an object relative to a reference
+/// state, as components along the axes of a local `frame` of the reference.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LocalState {
+    pub frame: LocalFrame,
+    pub position: Vec3,
+    pub velocity: Vec3,
+}
+
+// Rotation from the inertial frame to `frame`: the rows are the local axes.
+fn rotation(frame: LocalFrame, position: &Vec3, velocity: &Vec3) -> Mat3 {
+    let normal = unit(&cross(position, velocity));
+    match frame {
+        LocalFrame::Ric => {
+            let radial = unit(position);
+            [radial, cross(&normal, &radial), normal]
+        }
+        LocalFrame::Ntw => {
+            let tangential = unit(velocity);
+            [cross(&tangential, &normal), tangential, normal]
+        }
+        LocalFrame::Lvlh => {
+            let z = scale(&unit(position), -1.0);
+            let y = scale(&normal, -1.0);
+            [cross(&y, &z), y, z]
+        }
+    }
+}
+
+// Angular velocity of the local frames, r × v / r², in the inertial frame.
+fn angular_velocity(position: &Vec3, velocity: &Vec3) -> Vec3 {
+    scale(&cross(position, velocity), 1.0 / dot(position, position))
+}
+
+/// State of `state` relative to `reference`, both inertial, expressed in the
+/// local `frame` of `reference`.
+///
+/// The relative velocity is the one seen from the rotating local frame, so
+/// it excludes the transport term due to the rotation of the frame.
+pub fn inertial_to_local(
+    reference: &StateVector,
+    state: &StateVector,
+    frame: LocalFrame,
+) -> LocalState {
+    let r: Vec3 = reference.position().into();
+    let v: Vec3 = reference.velocity().into();
+    let m = rotation(frame, &r, &v);
+    let omega = angular_velocity(&r, &v);
+
+    let dr = sub(&state.position().into(), &r);
+    let dv = sub(&state.velocity().into(), &v);
+    let dv_rotating = sub(&dv, &cross(&omega, &dr));
+    LocalState {
+        frame,
+        position: mat_vec(&m, &dr),
+        velocity: mat_vec(&m, &dv_rotating),
+    }
+}
+
+/// Inverse of [`inertial_to_local`]: the inertial state at the relative
+/// state `local`, given in its local frame of `reference`.
+pub fn local_to_inertial(reference: &StateVector, local: &LocalState) -> StateVector {
+    let r: Vec3 = reference.position().into();
+    let v: Vec3 = reference.velocity().into();
+    let mt = transpose(&rotation(local.frame, &r, &v));
+    let omega = angular_velocity(&r, &v);
+
+    let dr = mat_vec(&mt, &local.position);
+    let dv = add(&mat_vec(&mt, &local.velocity), &cross(&omega, &dr));
+    StateVector::new(add(&r, &dr).into(), add(&v, &dv).into())
+}
+
+/// Difference `other - reference` between two SGP4 states in the radial,
+/// in-track and cross-track frame of `reference`, km and km/s.
+pub fn ric_difference(reference: &Sgp4Result, other: &Sgp4Result) -> LocalState {
+    inertial_to_local(&(*reference).into(), &(*other).into(), LocalFrame::Ric)
+}
+
+#[wasm_bindgen(js_name = "inertialToLocal")]
+pub fn js_inertial_to_local(
+    reference_position: &EciVec3,
+    reference_velocity: &EciVec3,
+    position: &EciVec3,
+    velocity: &EciVec3,
+    frame: LocalFrame,
+) -> JsValue {
+    let reference = StateVector::new(*reference_position, *reference_velocity);
+    let state = StateVector::new(*position, *velocity);
+    to_value(&inertial_to_local(&reference, &state, frame)).unwrap()
+}
+
+#[wasm_bindgen(js_name = "localToInertial")]
+pub fn js_local_to_inertial(
+    reference_position: &EciVec3,
+    reference_velocity: &EciVec3,
+    local: JsValue,
+) -> Result<JsValue, JsValue> {
+    let reference = StateVector::new(*reference_position, *reference_velocity);
+    let local: LocalState = from_value(local)?;
+    Ok(to_value(&local_to_inertial(&reference, &local)).unwrap())
+}
+
+/// [`ric_difference`] of two results of `sgp4`.
+#[wasm_bindgen(js_name = "ricDifference")]
+pub fn js_ric_difference(reference: JsValue, other: JsValue) -> Result<JsValue, JsValue> {
+    let reference: Sgp4Result = from_value(reference)?;
+    let other: Sgp4Result = from_value(other)?;
+    Ok(to_value(&ric_difference(&reference, &other)).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::twoline2satrec;
+    use crate::propagation::sgp4::sgp4;
+    use crate::vector::norm;
+
+    fn reference() -> StateVector {
+        // eccentric orbit, so the velocity is not normal to the position
+        StateVector::new(
+            EciVec3::new(6500.0, 1200.0, -300.0),
+            EciVec3::new(-1.5, 7.2, 3.1),
+        )
+    }
+
+    #[test]
+    fn test_ric_axes() {
+        let reference = reference();
+        let r: Vec3 = reference.position().into();
+        let v: Vec3 = reference.velocity().into();
+
+        // 10 km higher and 2 km ahead along the velocity
+        let ahead = StateVector::new(
+            add(&add(&r, &scale(&unit(&r), 10.0)), &scale(&unit(&v), 2.0)).into(),
+            *reference.velocity(),
+        );
+        let ric = inertial_to_local(&reference, &ahead, LocalFrame::Ric);
+        let ntw = inertial_to_local(&reference, &ahead, LocalFrame::Ntw);
+        let lvlh = inertial_to_local(&reference, &ahead, LocalFrame::Lvlh);
+
+        assert_eq!(ric.frame, LocalFrame::Ric);
+        assert!(ric.position[0] > 9.0 && ric.position[1] > 1.0);
+        assert!(ric.position[2].abs() < 1e-9);
+        assert!((ntw.position[1] - 2.0 - 10.0 * dot(&unit(&r), &unit(&v))).abs() < 1e-9);
+        assert!((lvlh.position[2] + ric.position[0]).abs() < 1e-9);
+        assert!((lvlh.position[0] - ric.position[1]).abs() < 1e-9);
+        assert!((lvlh.position[1] + ric.position[2]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ric_difference() {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let now = sgp4(&mut satrec, 0.0).unwrap();
+        let later = sgp4(&mut satrec, 1.0 / 60.0).unwrap();
+
+        let itself = ric_difference(&now, &now);
+        assert!(norm(&itself.position) < 1e-12 && norm(&itself.velocity) < 1e-15);
+
+        // one second later the ISS is 7.66 km further along its orbit and,
+        // in the rotating frame, almost at rest
+        let ric = ric_difference(&now, &later);
+        assert!((ric.position[1] - 7.66).abs() < 0.01);
+        assert!(ric.position[0].abs() < 0.05 && ric.position[2].abs() < 1e-3);
+        assert!(norm(&ric.velocity) < 1e-3);
+    }
+
+    #[test]
+    fn test_rigid_rotation_has_no_relative_velocity() {
+        // a point fixed in the rotating frame moves with v + ω × Δr
+        let reference = reference();
+        let r: Vec3 = reference.position().into();
+        let v: Vec3 = reference.velocity().into();
+        let omega = angular_velocity(&r, &v);
+        let offset = [3.0, -4.0, 5.0];
+        let state = StateVector::new(
+            add(&r, &offset).into(),
+            add(&v, &cross(&omega, &offset)).into(),
+        );
+
+        for frame in [LocalFrame::Ric, LocalFrame::Ntw, LocalFrame::Lvlh] {
+            let local = inertial_to_local(&reference, &state, frame);
+            assert!(norm(&local.velocity) < 1e-12);
+            assert!((norm(&local.position) - norm(&offset)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let reference = reference();
+        let state = StateVector::new(
+            EciVec3::new(6510.0, 1195.0, -290.0),
+            EciVec3::new(-1.49, 7.21, 3.08),
+        );
+        for frame in [LocalFrame::Ric, LocalFrame::Ntw, LocalFrame::Lvlh] {
+            let local = inertial_to_local(&reference, &state, frame);
+            let back = local_to_inertial(&reference, &local);
+            let dr = sub(&back.position().into(), &state.position().into());
+            let dv = sub(&back.velocity().into(), &state.velocity().into());
+            assert!(norm(&dr) < 1e-9 && norm(&dv) < 1e-12);
+        }
+    }
+}
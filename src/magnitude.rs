@@ -0,0 +1,149 @@
+use crate::constants::{MINUTES_PER_DAY, PI};
+use crate::eclipse::{eclipse, EclipseState};
+use crate::propagation::gstime::gstime;
+use crate::propagation::sgp4::{sgp4, Sgp4Error};
+use crate::sun::sun_position_ecf;
+use crate::transforms::{ecf_to_look_angles, eci_to_ecf, geodetic_to_ecf};
+use crate::vector::{dot, norm, sub, Vec3};
+use crate::{GeodeticLocation, SatRec};
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Range, in km, at which standard magnitudes are defined.
+const STANDARD_RANGE: f64 = 1000.0;
+
+/// Apparent visual magnitude of a satellite modelled as a diffuse
+/// (Lambertian) sphere.
+///
+/// `intrinsic_magnitude` is the standard magnitude at 1000 km range and 90°
+/// phase angle, `range` is in km and `phase_angle` (Sun-satellite-observer)
+/// in radians. At a phase angle of 180° only the unlit side faces the
+/// observer and the magnitude is infinite.
+#[wasm_bindgen(js_name = "visualMagnitude")]
+pub fn visual_magnitude(intrinsic_magnitude: f64, range: f64, phase_angle: f64) -> f64 {
+    if phase_angle >= PI {
+        return f64::INFINITY;
+    }
+    // phase function normalised to 1 at 90°: 0 at 180° and pi at 0°
+    let phase = (PI - phase_angle) * phase_angle.cos() + phase_angle.sin();
+    intrinsic_magnitude + 5.0 * (range / STANDARD_RANGE).log10() - 2.5 * phase.log10()
+}
+
+/// Sun-satellite-observer angle in radians from Earth-fixed positions.
+pub(crate) fn phase_angle(satellite: &Vec3, observer: &Vec3, sun: &Vec3) -> f64 {
+    let to_sun = sub(sun, satellite);
+    let to_observer = sub(observer, satellite);
+    (dot(&to_sun, &to_observer) / (norm(&to_sun) * norm(&to_observer)))
+        .clamp(-1.0, 1.0)
+        .acos()
+}
+
+/// Estimated visual magnitude of `satrec` seen from `observer` at Julian
+/// date `jd`, using the intrinsic magnitude attached to the element set.
+///
+/// Returns `None` when no intrinsic magnitude is set, the satellite is below
+/// the horizon or in the Earth's umbra, or none of its lit side faces the
+/// observer. In penumbra the brightness is scaled by the visible fraction of
+/// the solar disc.
+pub fn estimate_magnitude(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    jd: f64,
+) -> Result<Option<f64>, Sgp4Error> {
+    let intrinsic_magnitude = match satrec.intrinsic_magnitude() {
+        Some(magnitude) => magnitude,
+        None => return Ok(None),
+    };
+
+    let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+    let satellite_ecf = eci_to_ecf(result.position(), gstime(jd));
+    let look_angles = ecf_to_look_angles(observer, &satellite_ecf);
+    let illumination = eclipse(result.position(), jd);
+    if look_angles.elevation() < 0.0 || illumination.state == EclipseState::Umbra {
+        return Ok(None);
+    }
+
+    let phase = phase_angle(
+        &(&satellite_ecf).into(),
+        &(&geodetic_to_ecf(observer)).into(),
+        &(&sun_position_ecf(jd)).into(),
+    );
+    let magnitude = visual_magnitude(intrinsic_magnitude, look_angles.range_sat(), phase)
+        - 2.5 * (1.0 - illumination.shadow_fraction).log10();
+    if magnitude.is_finite() {
+        Ok(Some(magnitude))
+    } else {
+        Ok(None)
+    }
+}
+
+#[wasm_bindgen(js_name = "estimateMagnitude")]
+pub fn js_estimate_magnitude(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    jd: f64,
+) -> Result<Option<f64>, JsValue> {
+    estimate_magnitude(satrec, observer, jd).map_err(|sgp4_error| to_value(&sgp4_error).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEG2RAD;
+    use crate::io::twoline2satrec;
+    use crate::transforms::eci_to_geodetic;
+
+    #[test]
+    fn test_visual_magnitude() {
+        // standard conditions give back the intrinsic magnitude
+        assert!((visual_magnitude(-1.8, 1000.0, 90.0 * DEG2RAD) + 1.8).abs() < 1e-12);
+        // ten times farther is five magnitudes fainter
+        assert!((visual_magnitude(-1.8, 10000.0, 90.0 * DEG2RAD) - 3.2).abs() < 1e-12);
+        // a fully lit sphere is 2.5 log10(pi) brighter than a half-lit one
+        let full = visual_magnitude(0.0, 1000.0, 0.0);
+        assert!((full + 2.5 * PI.log10()).abs() < 1e-12);
+        assert!(visual_magnitude(0.0, 1000.0, 150.0 * DEG2RAD) > 2.0);
+        // nothing lit is seen at 180°
+        assert_eq!(visual_magnitude(0.0, 1000.0, PI), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_estimate_magnitude() {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let jd = satrec.jdsatepoch;
+        let result = sgp4(&mut satrec, 0.0).unwrap();
+        let gmst = gstime(jd);
+        let mut below = eci_to_geodetic(result.position(), gmst);
+        below.height = 0.0;
+        let mut opposite = below;
+        opposite.latitude = -below.latitude;
+        opposite.longitude += PI;
+
+        assert_eq!(estimate_magnitude(&mut satrec, &below, jd).unwrap(), None);
+
+        satrec.set_intrinsic_magnitude(Some(-1.8));
+        // never seen from the far side of the Earth
+        assert_eq!(
+            estimate_magnitude(&mut satrec, &opposite, jd).unwrap(),
+            None
+        );
+
+        // sunlit at epoch, seen from directly below
+        assert_eq!(eclipse(result.position(), jd).state, EclipseState::Sunlit);
+        let magnitude = estimate_magnitude(&mut satrec, &below, jd)
+            .unwrap()
+            .unwrap();
+        let satellite_ecf = eci_to_ecf(result.position(), gmst);
+        let range = ecf_to_look_angles(&below, &satellite_ecf).range_sat();
+        let phase = phase_angle(
+            &(&satellite_ecf).into(),
+            &(&geodetic_to_ecf(&below)).into(),
+            &(&sun_position_ecf(jd)).into(),
+        );
+        assert!((magnitude - visual_magnitude(-1.8, range, phase)).abs() < 1e-9);
+        assert!(magnitude < 0.0);
+    }
+}
@@ -0,0 +1,157 @@
+use crate::constants::{DEG2RAD, EARTH_RADIUS, PI};
+use crate::propagation::gstime::gstime;
+use crate::sun::sun_position_teme;
+use crate::transforms::{eci_to_ecf, mod_to_teme_matrix, teme_to_j2000};
+use crate::vector::{cross, dot, mat_vec, norm, Vec3};
+use crate::{EcfVec3, EciVec3};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/* -----------------------------------------------------------------------------
+ *
+ *                           function moon
+ *
+ *  this function calculates the geocentric equatorial (ijk) position vector
+ *    for the moon given the julian date. this is the low precision formula and
+ *    is valid for years between 1950 and 2050. the accuracy of the ecliptic
+ *    coordinates is about 0.3 degrees in longitude and 0.2 degrees in
+ *    latitude; the distance is good to about 0.1 percent.
+ *
+ *  author        : david vallado                  719-573-2600   27 may 2002
+ *
+ *  inputs          description                    range / units
+ *    jd          - julian date                    days from 4713 bc
+ *
+ *  outputs       :
+ *    rmoon       - mod position vector              km
+ *
+ *  references    :
+ *    vallado       2013, 288, alg 31, ex 5-3
+ * --------------------------------------------------------------------------- */
+fn moon_position_mod(jd: f64) -> Vec3 {
+    let ttdb = (jd - 2451545.0) / 36525.0;
+    let deg_sin = |deg: f64| (deg * DEG2RAD).sin();
+    let deg_cos = |deg: f64| (deg * DEG2RAD).cos();
+
+    let eclplong = (218.32 + 481267.8813 * ttdb + 6.29 * deg_sin(134.9 + 477198.85 * ttdb)
+        - 1.27 * deg_sin(259.2 - 413335.38 * ttdb)
+        + 0.66 * deg_sin(235.7 + 890534.23 * ttdb)
+        + 0.21 * deg_sin(269.9 + 954397.70 * ttdb)
+        - 0.19 * deg_sin(357.5 + 35999.05 * ttdb)
+        - 0.11 * deg_sin(186.6 + 966404.05 * ttdb))
+        * DEG2RAD;
+    let eclplat = (5.13 * deg_sin(93.3 + 483202.03 * ttdb)
+        + 0.28 * deg_sin(228.2 + 960400.87 * ttdb)
+        - 0.28 * deg_sin(318.3 + 6003.18 * ttdb)
+        - 0.17 * deg_sin(217.6 - 407332.20 * ttdb))
+        * DEG2RAD;
+    let hzparal = (0.9508
+        + 0.0518 * deg_cos(134.9 + 477198.85 * ttdb)
+        + 0.0095 * deg_cos(259.2 - 413335.38 * ttdb)
+        + 0.0078 * deg_cos(235.7 + 890534.23 * ttdb)
+        + 0.0028 * deg_cos(269.9 + 954397.70 * ttdb))
+        * DEG2RAD;
+    let obliquity = (23.439291 - 0.0130042 * ttdb) * DEG2RAD;
+
+    let l = eclplat.cos() * eclplong.cos();
+    let m = obliquity.cos() * eclplat.cos() * eclplong.sin() - obliquity.sin() * eclplat.sin();
+    let n = obliquity.sin() * eclplat.cos() * eclplong.sin() + obliquity.cos() * eclplat.sin();
+
+    let magr = EARTH_RADIUS / hzparal.sin();
+    [magr * l, magr * m, magr * n]
+}
+
+/// Geocentric position of the Moon in the TEME frame, km, at Julian date `jd`.
+///
+/// Uses the Astronomical Almanac low-precision lunar coordinates (Vallado
+/// alg. 31): about 0.3° in direction and 0.1% in distance between 1950 and
+/// 2050.
+#[wasm_bindgen(js_name = "moonPositionTeme")]
+pub fn moon_position_teme(jd: f64) -> EciVec3 {
+    mat_vec(&mod_to_teme_matrix(jd), &moon_position_mod(jd)).into()
+}
+
+/// Geocentric position of the Moon in the J2000 frame, km.
+#[wasm_bindgen(js_name = "moonPositionJ2000")]
+pub fn moon_position_j2000(jd: f64) -> EciVec3 {
+    teme_to_j2000(&moon_position_teme(jd), jd)
+}
+
+/// Position of the Moon in the Earth-fixed frame, km.
+#[wasm_bindgen(js_name = "moonPositionEcf")]
+pub fn moon_position_ecf(jd: f64) -> EcfVec3 {
+    eci_to_ecf(&moon_position_teme(jd), gstime(jd))
+}
+
+/// Illumination of the Moon as seen from the centre of the Earth.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoonPhase {
+    /// Sun-Moon-Earth angle in radians: 0 at full moon, pi at new moon.
+    pub phase_angle: f64,
+    /// Fraction of the disc that is lit, 0 .. 1.
+    pub illuminated_fraction: f64,
+    /// Geocentric angle between the Sun and the Moon in radians, 0 .. pi.
+    pub elongation: f64,
+    /// True between new and full moon.
+    pub waxing: bool,
+}
+
+/// Phase of the Moon at Julian date `jd`.
+#[wasm_bindgen(js_name = "moonPhase")]
+pub fn moon_phase(jd: f64) -> MoonPhase {
+    let sun: Vec3 = (&sun_position_teme(jd)).into();
+    let moon: Vec3 = (&moon_position_teme(jd)).into();
+    let sun_distance = norm(&sun);
+    let moon_distance = norm(&moon);
+
+    let elongation = (dot(&sun, &moon) / (sun_distance * moon_distance))
+        .clamp(-1.0, 1.0)
+        .acos();
+    let phase_angle =
+        (sun_distance * elongation.sin()).atan2(moon_distance - sun_distance * elongation.cos());
+
+    // the Moon moves eastward along the ecliptic, so it is waxing while it
+    // is east of the Sun
+    let obliquity = (23.439291 - 0.0130042 * (jd - 2451545.0) / 36525.0) * DEG2RAD;
+    let ecliptic_pole = [0.0, -obliquity.sin(), obliquity.cos()];
+    let waxing = dot(&cross(&sun, &moon), &ecliptic_pole) > 0.0;
+
+    MoonPhase {
+        phase_angle,
+        illuminated_fraction: 0.5 * (1.0 + phase_angle.cos()),
+        elongation: elongation.clamp(0.0, PI),
+        waxing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext::jday;
+
+    #[test]
+    fn test_moon_phases() {
+        let new_moon = moon_phase(jday(2024.0, 4.0, 8.0, 18.0, 21.0, 0.0, 0.0));
+        let first_quarter = moon_phase(jday(2024.0, 4.0, 15.0, 19.0, 13.0, 0.0, 0.0));
+        let full_moon = moon_phase(jday(2024.0, 4.0, 23.0, 23.0, 49.0, 0.0, 0.0));
+        let last_quarter = moon_phase(jday(2024.0, 5.0, 1.0, 11.0, 27.0, 0.0, 0.0));
+
+        assert!(new_moon.illuminated_fraction < 0.01);
+        assert!((first_quarter.illuminated_fraction - 0.5).abs() < 0.02);
+        assert!(first_quarter.waxing);
+        assert!(full_moon.illuminated_fraction > 0.99);
+        assert!((last_quarter.illuminated_fraction - 0.5).abs() < 0.02);
+        assert!(!last_quarter.waxing);
+    }
+
+    #[test]
+    fn test_moon_distance() {
+        // perigee 2024-04-07 17:50 UTC at 358 850 km, apogee 2024-04-20 02:09 UTC at 405 623 km
+        let perigee = moon_position_teme(jday(2024.0, 4.0, 7.0, 17.0, 50.0, 0.0, 0.0));
+        let apogee = moon_position_teme(jday(2024.0, 4.0, 20.0, 2.0, 9.0, 0.0, 0.0));
+        assert!((norm(&(&perigee).into()) - 358850.0).abs() < 500.0);
+        assert!((norm(&(&apogee).into()) - 405623.0).abs() < 500.0);
+    }
+}
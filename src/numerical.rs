@@ -0,0 +1,627 @@
+use crate::constants::{GravityModel, AU, MINUTES_PER_DAY, OMEGA_EARTH};
+use crate::eclipse::eclipse;
+use crate::moon::moon_position_teme;
+use crate::propagation::gstime::gstime;
+use crate::propagation::sgp4::sgp4;
+use crate::propagator::{Propagator, PropagatorError, StateVector};
+use crate::sun::sun_position_teme;
+use crate::transforms::{eci_to_geodetic, state_to_teme, Frame};
+use crate::vector::{add, cross, norm, scale, sub, Vec3};
+use crate::{EciVec3, SatRec};
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::prelude::*;
+
+/// Zonal harmonics beyond J4, from EGM96; they are used with every gravity
+/// model.
+const J5: f64 = -2.27296e-7;
+const J6: f64 = 5.40681e-7;
+
+/// Gravitational parameters of the Sun and the Moon, km^3 / s^2 (DE430).
+const MU_SUN: f64 = 1.32712440041e11;
+const MU_MOON: f64 = 4902.800066;
+
+/// Solar radiation pressure at 1 AU, N / m^2.
+const SOLAR_PRESSURE: f64 = 4.56e-6;
+
+/// Base altitude (km), density (kg/m^3) and scale height (km) of the
+/// exponential atmosphere, Vallado table 8-4.
+const ATMOSPHERE: [(f64, f64, f64); 28] = [
+    (0.0, 1.225, 7.249),
+    (25.0, 3.899e-2, 6.349),
+    (30.0, 1.774e-2, 6.682),
+    (40.0, 3.972e-3, 7.554),
+    (50.0, 1.057e-3, 8.382),
+    (60.0, 3.206e-4, 7.714),
+    (70.0, 8.770e-5, 6.549),
+    (80.0, 1.905e-5, 5.799),
+    (90.0, 3.396e-6, 5.382),
+    (100.0, 5.297e-7, 5.877),
+    (110.0, 9.661e-8, 7.263),
+    (120.0, 2.438e-8, 9.473),
+    (130.0, 8.484e-9, 12.636),
+    (140.0, 3.845e-9, 16.149),
+    (150.0, 2.070e-9, 22.523),
+    (180.0, 5.464e-10, 29.740),
+    (200.0, 2.789e-10, 37.105),
+    (250.0, 7.248e-11, 45.546),
+    (300.0, 2.418e-11, 53.628),
+    (350.0, 9.518e-12, 53.298),
+    (400.0, 3.725e-12, 58.515),
+    (450.0, 1.585e-12, 60.828),
+    (500.0, 6.967e-13, 63.822),
+    (600.0, 1.454e-13, 71.835),
+    (700.0, 3.614e-14, 88.667),
+    (800.0, 1.170e-14, 124.64),
+    (900.0, 5.245e-15, 181.05),
+    (1000.0, 3.019e-15, 268.00),
+];
+
+/// Runge-Kutta-Fehlberg 7(8) nodes, coefficients and weights.
+#[rustfmt::skip]
+const C: [f64; 13] = [
+    0.0, 2.0 / 27.0, 1.0 / 9.0, 1.0 / 6.0, 5.0 / 12.0, 1.0 / 2.0, 5.0 / 6.0, 1.0 / 6.0,
+    2.0 / 3.0, 1.0 / 3.0, 1.0, 0.0, 1.0,
+];
+
+#[rustfmt::skip]
+const A: [[f64; 12]; 13] = [
+    [0.0; 12],
+    [2.0 / 27.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 36.0, 1.0 / 12.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 24.0, 0.0, 1.0 / 8.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [5.0 / 12.0, 0.0, -25.0 / 16.0, 25.0 / 16.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 20.0, 0.0, 0.0, 1.0 / 4.0, 1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [-25.0 / 108.0, 0.0, 0.0, 125.0 / 108.0, -65.0 / 27.0, 125.0 / 54.0,
+        0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [31.0 / 300.0, 0.0, 0.0, 0.0, 61.0 / 225.0, -2.0 / 9.0, 13.0 / 900.0,
+        0.0, 0.0, 0.0, 0.0, 0.0],
+    [2.0, 0.0, 0.0, -53.0 / 6.0, 704.0 / 45.0, -107.0 / 9.0, 67.0 / 90.0, 3.0,
+        0.0, 0.0, 0.0, 0.0],
+    [-91.0 / 108.0, 0.0, 0.0, 23.0 / 108.0, -976.0 / 135.0, 311.0 / 54.0, -19.0 / 60.0,
+        17.0 / 6.0, -1.0 / 12.0, 0.0, 0.0, 0.0],
+    [2383.0 / 4100.0, 0.0, 0.0, -341.0 / 164.0, 4496.0 / 1025.0, -301.0 / 82.0,
+        2133.0 / 4100.0, 45.0 / 82.0, 45.0 / 164.0, 18.0 / 41.0, 0.0, 0.0],
+    [3.0 / 205.0, 0.0, 0.0, 0.0, 0.0, -6.0 / 41.0, -3.0 / 205.0, -3.0 / 41.0, 3.0 / 41.0,
+        6.0 / 41.0, 0.0, 0.0],
+    [-1777.0 / 4100.0, 0.0, 0.0, -341.0 / 164.0, 4496.0 / 1025.0, -289.0 / 82.0,
+        2193.0 / 4100.0, 51.0 / 82.0, 33.0 / 164.0, 12.0 / 41.0, 0.0, 1.0],
+];
+
+/// Weights of the eighth-order solution. The seventh-order one differs by
+/// 41/840 (k1 + k11 - k12 - k13), which gives the error estimate.
+#[rustfmt::skip]
+const B: [f64; 13] = [
+    0.0, 0.0, 0.0, 0.0, 0.0, 34.0 / 105.0, 9.0 / 35.0, 9.0 / 35.0, 9.0 / 280.0, 9.0 / 280.0,
+    0.0, 41.0 / 840.0, 41.0 / 840.0,
+];
+
+/// First trial step, seconds.
+const INITIAL_STEP: f64 = 60.0;
+
+/// Smallest step, seconds; the integration fails rather than go below it.
+const MIN_STEP: f64 = 1e-6;
+
+/// Smallest relative tolerance the steps can meet in double precision.
+const MIN_TOLERANCE: f64 = 1e-15;
+
+/// Bounds on the factor by which the step changes between two steps.
+const MIN_STEP_FACTOR: f64 = 0.2;
+const MAX_STEP_FACTOR: f64 = 5.0;
+
+type State = [f64; 6];
+
+/// Forces acting on a numerically propagated satellite. Area-to-mass ratios
+/// are in m^2 / kg.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ForceModel {
+    pub gravity: GravityModel,
+    /// Highest zonal harmonic, 2 to 6; below 2 the Earth is a point mass.
+    pub zonal_degree: usize,
+    /// Drag in an exponential atmosphere rotating with the Earth.
+    pub drag: bool,
+    pub drag_coefficient: f64,
+    pub drag_area_to_mass: f64,
+    /// Point-mass attraction of the Sun.
+    pub sun: bool,
+    /// Point-mass attraction of the Moon.
+    pub moon: bool,
+    /// Solar radiation pressure on a cannonball, dimmed in the Earth's
+    /// penumbra and cut off in its umbra.
+    pub solar_radiation_pressure: bool,
+    pub reflectivity: f64,
+    pub srp_area_to_mass: f64,
+}
+
+impl Default for ForceModel {
+    fn default() -> ForceModel {
+        ForceModel {
+            gravity: GravityModel::Wgs84,
+            zonal_degree: 6,
+            drag: true,
+            drag_coefficient: 2.2,
+            drag_area_to_mass: 0.01,
+            sun: true,
+            moon: true,
+            solar_radiation_pressure: true,
+            reflectivity: 1.3,
+            srp_area_to_mass: 0.01,
+        }
+    }
+}
+
+impl ForceModel {
+    /// Two-body motion only.
+    pub fn point_mass(gravity: GravityModel) -> ForceModel {
+        ForceModel {
+            gravity,
+            zonal_degree: 0,
+            drag: false,
+            sun: false,
+            moon: false,
+            solar_radiation_pressure: false,
+            ..ForceModel::default()
+        }
+    }
+
+    fn zonal(&self, n: usize) -> f64 {
+        match n {
+            2 => self.gravity.j2(),
+            3 => self.gravity.j3(),
+            4 => self.gravity.j4(),
+            5 => J5,
+            6 => J6,
+            _ => 0.0,
+        }
+    }
+
+    // Central attraction and zonal harmonics, from the gradient of
+    // mu/r (1 - Σ Jn (R/r)^n Pn(sin φ)).
+    fn gravity(&self, r: &Vec3) -> Vec3 {
+        let mu = self.gravity.mu();
+        let distance = norm(r);
+        let radial = scale(r, 1.0 / distance);
+        let mut acceleration = scale(&radial, -mu / (distance * distance));
+
+        let s = radial[2];
+        let (mut p0, mut p1) = (1.0, s);
+        let mut dp1 = 1.0;
+        let ratio = self.gravity.radius() / distance;
+        for n in 2..=self.zonal_degree.min(6) {
+            let nf = n as f64;
+            let p = ((2.0 * nf - 1.0) * s * p1 - (nf - 1.0) * p0) / nf;
+            let dp = s * dp1 + nf * p1;
+            let k = mu / (distance * distance) * self.zonal(n) * ratio.powi(n as i32);
+            acceleration = add(
+                &acceleration,
+                &scale(&radial, k * ((nf + 1.0) * p + s * dp)),
+            );
+            acceleration[2] -= k * dp;
+            (p0, p1) = (p1, p);
+            dp1 = dp;
+        }
+        acceleration
+    }
+
+    // Acceleration at TEME position `r` (km) and velocity `v` (km/s) at
+    // Julian date `jd`, km/s^2.
+    fn acceleration(&self, jd: f64, r: &Vec3, v: &Vec3) -> Vec3 {
+        let mut acceleration = self.gravity(r);
+
+        if self.drag {
+            let height = eci_to_geodetic(&(*r).into(), gstime(jd)).height;
+            let relative = sub(v, &cross(&[0.0, 0.0, OMEGA_EARTH], r));
+            let speed = norm(&relative);
+            // ρ v² A/m is in m/s^2 with v in m/s, 1000 v² km/s^2 with v in km/s
+            let k = -0.5
+                * self.drag_coefficient
+                * self.drag_area_to_mass
+                * density(height)
+                * speed
+                * 1000.0;
+            acceleration = add(&acceleration, &scale(&relative, k));
+        }
+
+        if self.sun || self.solar_radiation_pressure {
+            let sun: Vec3 = (&sun_position_teme(jd)).into();
+            if self.sun {
+                acceleration = add(&acceleration, &third_body(MU_SUN, &sun, r));
+            }
+            if self.solar_radiation_pressure {
+                let to_sun = sub(&sun, r);
+                let distance = norm(&to_sun);
+                let illumination = 1.0 - eclipse(&(*r).into(), jd).shadow_fraction;
+                let k = -SOLAR_PRESSURE
+                    * self.reflectivity
+                    * self.srp_area_to_mass
+                    * illumination
+                    * (AU / distance).powi(2)
+                    / 1000.0;
+                acceleration = add(&acceleration, &scale(&to_sun, k / distance));
+            }
+        }
+
+        if self.moon {
+            let moon: Vec3 = (&moon_position_teme(jd)).into();
+            acceleration = add(&acceleration, &third_body(MU_MOON, &moon, r));
+        }
+
+        acceleration
+    }
+}
+
+// Density of the exponential atmosphere at geodetic `height` (km), kg/m^3.
+fn density(height: f64) -> f64 {
+    let height = height.max(0.0);
+    let &(base, density, scale_height) = ATMOSPHERE
+        .iter()
+        .rev()
+        .find(|(base, _, _)| height >= *base)
+        .unwrap_or(&ATMOSPHERE[0]);
+    density * (-(height - base) / scale_height).exp()
+}
+
+// Attraction of a body of gravitational parameter `mu` at `body`, relative
+// to the Earth, on a satellite at `r`.
+fn third_body(mu: f64, body: &Vec3, r: &Vec3) -> Vec3 {
+    let d = sub(body, r);
+    let dd = norm(&d);
+    let bb = norm(body);
+    sub(
+        &scale(&d, mu / (dd * dd * dd)),
+        &scale(body, mu / (bb * bb * bb)),
+    )
+}
+
+/// Propagator integrating the equations of motion in TEME with an adaptive
+/// Runge-Kutta-Fehlberg 7(8) scheme, under the forces of a [`ForceModel`].
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct NumericalPropagator {
+    epoch: f64,
+    initial: State,
+    forces: ForceModel,
+    tolerance: f64,
+    // last state reached, kept to continue from when dates move away from
+    // the epoch
+    current: (f64, State),
+    step: f64,
+    satnum: Option<String>,
+}
+
+#[wasm_bindgen]
+impl NumericalPropagator {
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> f64 {
+        self.epoch
+    }
+}
+
+impl NumericalPropagator {
+    /// Propagator through the state `position` (km), `velocity` (km/s) at
+    /// Julian date `epoch`, given in `frame`. Each step keeps the estimated
+    /// local error of every component below `tolerance`, relative to its
+    /// magnitude; 1e-12 keeps a day of low Earth orbit to a few metres.
+    /// Fails with [`PropagatorError::InvalidTolerance`] below 1e-15.
+    pub fn new(
+        epoch: f64,
+        position: &EciVec3,
+        velocity: &EciVec3,
+        frame: Frame,
+        forces: ForceModel,
+        tolerance: f64,
+    ) -> Result<NumericalPropagator, PropagatorError> {
+        if !(tolerance >= MIN_TOLERANCE && tolerance.is_finite()) {
+            return Err(PropagatorError::InvalidTolerance);
+        }
+        let (r, v) = state_to_teme(frame, epoch, &position.into(), &velocity.into());
+        let initial = [r[0], r[1], r[2], v[0], v[1], v[2]];
+        Ok(NumericalPropagator {
+            epoch,
+            initial,
+            forces,
+            tolerance,
+            current: (epoch, initial),
+            step: INITIAL_STEP,
+            satnum: None,
+        })
+    }
+
+    /// Propagator through the TEME state of `satrec` at Julian date `jd`,
+    /// carrying its catalog number.
+    pub fn from_satrec(
+        satrec: &mut SatRec,
+        jd: f64,
+        forces: ForceModel,
+        tolerance: f64,
+    ) -> Result<NumericalPropagator, PropagatorError> {
+        let state = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+        Ok(NumericalPropagator {
+            satnum: Some(satrec.satnum()),
+            ..NumericalPropagator::new(
+                jd,
+                state.position(),
+                state.velocity(),
+                Frame::Teme,
+                forces,
+                tolerance,
+            )?
+        })
+    }
+
+    fn derivative(&self, t: f64, y: &State) -> State {
+        let jd = self.epoch + t / 86400.0;
+        let a = self
+            .forces
+            .acceleration(jd, &[y[0], y[1], y[2]], &[y[3], y[4], y[5]]);
+        [y[3], y[4], y[5], a[0], a[1], a[2]]
+    }
+
+    // One Runge-Kutta-Fehlberg step of `h` seconds from `y` at `t`: the
+    // eighth-order solution and the error of the seventh-order one.
+    fn rkf78(&self, t: f64, y: &State, h: f64) -> (State, State) {
+        let mut k = [[0.0; 6]; 13];
+        for stage in 0..13 {
+            let mut ys = *y;
+            for (j, kj) in k.iter().enumerate().take(stage) {
+                for i in 0..6 {
+                    ys[i] += h * A[stage][j] * kj[i];
+                }
+            }
+            k[stage] = self.derivative(t + C[stage] * h, &ys);
+        }
+        let mut next = *y;
+        let mut error = [0.0; 6];
+        for i in 0..6 {
+            for (stage, b) in B.iter().enumerate() {
+                next[i] += h * b * k[stage][i];
+            }
+            error[i] = h * 41.0 / 840.0 * (k[0][i] + k[10][i] - k[11][i] - k[12][i]);
+        }
+        (next, error)
+    }
+
+    // Integrate from the state `y` at `t` to `target`, seconds from the
+    // epoch.
+    fn integrate(
+        &mut self,
+        mut t: f64,
+        mut y: State,
+        target: f64,
+    ) -> Result<State, PropagatorError> {
+        let direction = (target - t).signum();
+        let mut h = self.step.abs() * direction;
+        while (target - t) * direction > 0.0 {
+            if (t + h - target) * direction > 0.0 {
+                h = target - t;
+            }
+            let (next, error) = self.rkf78(t, &y, h);
+            let ratio = (0..6)
+                .map(|i| error[i].abs() / (self.tolerance * (1.0 + y[i].abs().max(next[i].abs()))))
+                .fold(0.0, f64::max);
+            let factor = (0.9 * ratio.powf(-1.0 / 8.0)).clamp(MIN_STEP_FACTOR, MAX_STEP_FACTOR);
+            if ratio <= 1.0 {
+                t += h;
+                y = next;
+                if norm(&[y[0], y[1], y[2]]) < self.forces.gravity.radius() {
+                    return Err(PropagatorError::Decayed);
+                }
+                if t != target {
+                    self.step = h.abs();
+                }
+            } else if h.abs() < MIN_STEP {
+                return Err(PropagatorError::StepSizeTooSmall);
+            }
+            h *= factor;
+        }
+        Ok(y)
+    }
+}
+
+impl Propagator for NumericalPropagator {
+    fn state_at(&mut self, jd: f64) -> Result<StateVector, PropagatorError> {
+        let target = (jd - self.epoch) * 86400.0;
+        let (current_jd, current_state) = self.current;
+        let current = (current_jd - self.epoch) * 86400.0;
+        // continue from the last state only when moving further away from
+        // the epoch, else start over from it; the steps taken, and so the
+        // result within the tolerance, depend on the earlier calls
+        let y = if target * current > 0.0 && target.abs() >= current.abs() {
+            let y = self.integrate(current, current_state, target)?;
+            self.current = (jd, y);
+            y
+        } else {
+            let y = self.integrate(0.0, self.initial, target)?;
+            self.current = (jd, y);
+            y
+        };
+        Ok(StateVector::new(
+            EciVec3::new(y[0], y[1], y[2]),
+            EciVec3::new(y[3], y[4], y[5]),
+        ))
+    }
+
+    fn valid_range(&self) -> (f64, f64) {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    fn frame(&self) -> Frame {
+        Frame::Teme
+    }
+
+    fn satnum(&self) -> Option<String> {
+        self.satnum.clone()
+    }
+}
+
+#[wasm_bindgen(js_name = "numericalFromSatRec")]
+pub fn js_numerical_from_satrec(
+    satrec: &mut SatRec,
+    jd: f64,
+    forces: JsValue,
+    tolerance: f64,
+) -> Result<NumericalPropagator, JsValue> {
+    let forces: ForceModel = if forces.is_undefined() || forces.is_null() {
+        ForceModel::default()
+    } else {
+        from_value(forces)?
+    };
+    NumericalPropagator::from_satrec(satrec, jd, forces, tolerance)
+        .map_err(|error| to_value(&error).unwrap())
+}
+
+#[wasm_bindgen(js_name = "numericalStateAt")]
+pub fn js_numerical_state_at(
+    propagator: &mut NumericalPropagator,
+    jd: f64,
+) -> Result<JsValue, JsValue> {
+    match propagator.state_at(jd) {
+        Ok(state) => Ok(to_value(&state).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TWO_PI;
+    use crate::elements::rv2coe;
+    use crate::io::twoline2satrec;
+    use crate::vector::dot;
+    use crate::KeplerPropagator;
+
+    fn iss() -> SatRec {
+        twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        )
+    }
+
+    fn position(propagator: &mut impl Propagator, jd: f64) -> Vec3 {
+        propagator.state_at(jd).unwrap().position().into()
+    }
+
+    #[test]
+    fn test_point_mass_matches_kepler() {
+        let mut satrec = iss();
+        let jd = satrec.jdsatepoch;
+        let forces = ForceModel::point_mass(GravityModel::Wgs72);
+        let mut numerical =
+            NumericalPropagator::from_satrec(&mut satrec, jd, forces, 1e-13).unwrap();
+        let mut kepler = KeplerPropagator::from_satrec(&mut satrec, jd).unwrap();
+
+        for days in [0.5, -0.3, 1.0] {
+            let difference = sub(
+                &position(&mut numerical, jd + days),
+                &position(&mut kepler, jd + days),
+            );
+            assert!(norm(&difference) < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_j2_nodal_regression() {
+        let mut satrec = iss();
+        let jd = satrec.jdsatepoch;
+        let forces = ForceModel {
+            zonal_degree: 2,
+            drag: false,
+            sun: false,
+            moon: false,
+            solar_radiation_pressure: false,
+            ..ForceModel::default()
+        };
+        let mut numerical =
+            NumericalPropagator::from_satrec(&mut satrec, jd, forces, 1e-12).unwrap();
+
+        let raan =
+            |state: &StateVector| rv2coe(state.position(), state.velocity(), GravityModel::Wgs84);
+        let start = raan(&numerical.state_at(jd).unwrap());
+        let end = raan(&numerical.state_at(jd + 1.0).unwrap());
+
+        // secular rate -3/2 n J2 (R/p)^2 cos i, about -5°/day for the ISS
+        let n = (GravityModel::Wgs84.mu() / start.a.powi(3)).sqrt();
+        let expected = -1.5
+            * n
+            * GravityModel::Wgs84.j2()
+            * (GravityModel::Wgs84.radius() / start.p).powi(2)
+            * start.incl.cos()
+            * 86400.0;
+        let drift = (end.raan - start.raan + TWO_PI) % TWO_PI - TWO_PI;
+        assert!((drift / expected - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_drag_lowers_orbit() {
+        let mut satrec = iss();
+        let jd = satrec.jdsatepoch;
+        let without_drag = ForceModel {
+            drag: false,
+            ..ForceModel::default()
+        };
+        let mut full =
+            NumericalPropagator::from_satrec(&mut satrec, jd, ForceModel::default(), 1e-11)
+                .unwrap();
+        let mut conservative =
+            NumericalPropagator::from_satrec(&mut satrec, jd, without_drag, 1e-11).unwrap();
+
+        let energy = |state: StateVector| {
+            let r: Vec3 = state.position().into();
+            let v: Vec3 = state.velocity().into();
+            0.5 * dot(&v, &v) - GravityModel::Wgs84.mu() / norm(&r)
+        };
+        let lost = energy(conservative.state_at(jd + 1.0).unwrap())
+            - energy(full.state_at(jd + 1.0).unwrap());
+        assert!(lost > 0.0);
+
+        // the full model stays within tens of kilometres of SGP4 over a day
+        let difference = sub(
+            &position(&mut full, jd + 1.0),
+            &position(&mut satrec, jd + 1.0),
+        );
+        assert!(norm(&difference) < 50.0);
+    }
+
+    #[test]
+    fn test_density_and_third_body() {
+        assert!((density(0.0) - 1.225).abs() < 1e-12);
+        assert!((density(400.0) / 3.725e-12 - 1.0).abs() < 1e-12);
+        assert!(density(420.0) < density(400.0) && density(420.0) > density(450.0));
+
+        // the lunar tide at the ISS is about 1e-9 km/s^2
+        let moon = [384400.0, 0.0, 0.0];
+        let tide = norm(&third_body(MU_MOON, &moon, &[6778.0, 0.0, 0.0]));
+        assert!(tide > 5e-10 && tide < 2e-9);
+    }
+
+    #[test]
+    fn test_call_history_within_tolerance() {
+        let mut satrec = iss();
+        let jd = satrec.jdsatepoch;
+        let mut a = NumericalPropagator::from_satrec(&mut satrec, jd, ForceModel::default(), 1e-12)
+            .unwrap();
+        let mut b = a.clone();
+        let direct = position(&mut a, jd + 0.2);
+        position(&mut b, jd + 0.1);
+        let stepped = position(&mut b, jd + 0.2);
+        assert!(norm(&sub(&direct, &stepped)) < 1e-4);
+    }
+    #[test]
+    fn test_invalid_tolerance_and_decay() {
+        let position = EciVec3::new(6478.0, 0.0, 0.0);
+        let forces = ForceModel::point_mass(GravityModel::Wgs84);
+        for tolerance in [0.0, -1e-12, 1e-16, f64::NAN] {
+            let propagator =
+                NumericalPropagator::new(0.0, &position, &position, Frame::Teme, forces, tolerance);
+            assert_eq!(propagator.err(), Some(PropagatorError::InvalidTolerance));
+        }
+
+        // at rest 100 km up, the satellite falls in a few minutes
+        let at_rest = EciVec3::new(0.0, 0.0, 0.0);
+        let mut falling =
+            NumericalPropagator::new(0.0, &position, &at_rest, Frame::Teme, forces, 1e-12).unwrap();
+        assert!(falling.state_at(60.0 / 86400.0).is_ok());
+        assert_eq!(falling.state_at(0.1).err(), Some(PropagatorError::Decayed));
+    }
+}
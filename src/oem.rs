@@ -0,0 +1,750 @@
+//! CCSDS Orbit Ephemeris Messages (CCSDS 502.0-B-3) in the keyword-value
+//! (KVN) and XML notations. Only Earth-centred segments in UTC are handled;
+//! covariance blocks are skipped when reading.
+
+use crate::ephemeris::{Ephemeris, Interpolation};
+use crate::ext::jday_date;
+use crate::propagator::{Propagator, PropagatorError};
+use crate::transforms::Frame;
+use crate::vector::Vec3;
+use crate::SatRec;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::to_value;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+const VERSION: &str = "2.0";
+const ORIGINATOR: &str = "satellite";
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+/// Number of samples in each interpolation window of written messages, and
+/// of read messages that do not specify one.
+const DEFAULT_ORDER: usize = 8;
+
+/// Highest INTERPOLATION_DEGREE read; longer windows are of no use on
+/// ephemeris samples.
+const MAX_DEGREE: usize = 32;
+
+/// Why an OEM could not be read or written.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum OemError {
+    /// A mandatory keyword is absent; the payload names it.
+    Missing(String),
+    /// A line or value could not be parsed, is out of order or, when
+    /// writing, is a date beyond the calendar; the payload quotes it.
+    Invalid(String),
+    /// A reference frame, centre or time system that cannot be represented.
+    Unsupported(String),
+}
+
+/// Metadata of an OEM segment. Times are Julian dates in UTC.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OemMetadata {
+    pub object_name: String,
+    /// International designator, such as 1998-067A.
+    pub object_id: String,
+    pub center_name: String,
+    pub ref_frame: Frame,
+    pub time_system: String,
+    pub start_time: f64,
+    pub stop_time: f64,
+}
+
+/// A block of states sharing one set of metadata.
+#[derive(Clone, Debug)]
+pub struct OemSegment {
+    pub metadata: OemMetadata,
+    pub ephemeris: Ephemeris,
+}
+
+/// An Orbit Ephemeris Message.
+#[derive(Clone, Debug)]
+pub struct Oem {
+    pub originator: String,
+    /// Julian date at which the message was created.
+    pub creation_date: f64,
+    pub segments: Vec<OemSegment>,
+}
+
+// CCSDS name of `frame`. The Earth-fixed frame of this crate rotates about
+// the TEME pole without polar motion, which is true-of-date rotating.
+fn frame_name(frame: Frame) -> &'static str {
+    match frame {
+        Frame::Teme => "TEME",
+        Frame::J2000 => "EME2000",
+        Frame::Ecef => "TDR",
+    }
+}
+
+// Frame of a CCSDS REF_FRAME value. GCRF is read as EME2000, which it
+// matches to some 20 mas, and the ITRF realisations as the Earth-fixed frame
+// without polar motion.
+fn parse_frame(name: &str) -> Result<Frame, OemError> {
+    match name {
+        "TEME" => Ok(Frame::Teme),
+        "EME2000" | "J2000" | "GCRF" => Ok(Frame::J2000),
+        "TDR" | "GRC" => Ok(Frame::Ecef),
+        _ if name.starts_with("ITRF") => Ok(Frame::Ecef),
+        _ => Err(OemError::Unsupported(name.to_string())),
+    }
+}
+
+/// ISO 8601 calendar form of Julian date `jd`, UTC, to the microsecond.
+pub(crate) fn format_epoch(jd: f64) -> Result<String, OemError> {
+    let micros = ((jd - UNIX_EPOCH_JD) * 86400e6).round() as i64;
+    let datetime = DateTime::from_timestamp_micros(micros)
+        .filter(|_| jd.is_finite())
+        .ok_or_else(|| OemError::Invalid(jd.to_string()))?;
+    Ok(datetime.format("%Y-%m-%dT%H:%M:%S%.6f").to_string())
+}
+
+// Julian date of a CCSDS epoch, in calendar or day-of-year form.
+fn parse_epoch(text: &str) -> Result<f64, OemError> {
+    let text = text.trim().trim_end_matches('Z');
+    let datetime = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%jT%H:%M:%S%.f"))
+        .map_err(|_| OemError::Invalid(text.to_string()))?;
+    let micros = datetime.and_utc().timestamp_micros();
+    Ok(UNIX_EPOCH_JD + micros as f64 / 86400e6)
+}
+
+fn parse_number(text: &str) -> Result<f64, OemError> {
+    text.trim()
+        .parse()
+        .map_err(|_| OemError::Invalid(text.to_string()))
+}
+
+// INTERPOLATION and INTERPOLATION_DEGREE of `ephemeris`.
+fn interpolation_keywords(ephemeris: &Ephemeris) -> (&'static str, usize) {
+    match ephemeris.interpolation() {
+        Interpolation::Lagrange => ("LAGRANGE", ephemeris.order() - 1),
+        Interpolation::Hermite => ("HERMITE", 2 * ephemeris.order() - 1),
+    }
+}
+
+// Interpolation scheme and window length from the optional keywords.
+fn parse_interpolation(
+    method: Option<&str>,
+    degree: Option<&str>,
+) -> Result<(Interpolation, usize), OemError> {
+    let degree = degree
+        .map(|d| match d.trim().parse::<usize>() {
+            Ok(degree) if degree <= MAX_DEGREE => Ok(degree),
+            _ => Err(OemError::Invalid(d.to_string())),
+        })
+        .transpose()?;
+    Ok(match (method, degree) {
+        (Some("HERMITE"), Some(degree)) => (Interpolation::Hermite, degree.div_ceil(2)),
+        (Some("HERMITE"), None) => (Interpolation::Hermite, DEFAULT_ORDER / 2),
+        (Some("LINEAR"), _) => (Interpolation::Lagrange, 2),
+        (_, Some(degree)) => (Interpolation::Lagrange, degree + 1),
+        (_, None) => (Interpolation::Lagrange, DEFAULT_ORDER),
+    })
+}
+
+// Segment from its metadata keywords and states.
+fn build_segment(
+    keywords: &HashMap<String, String>,
+    times: Vec<f64>,
+    positions: Vec<Vec3>,
+    velocities: Vec<Vec3>,
+) -> Result<OemSegment, OemError> {
+    let get = |key: &str| {
+        keywords
+            .get(key)
+            .map(|value| value.as_str())
+            .ok_or_else(|| OemError::Missing(key.to_string()))
+    };
+    let center_name = get("CENTER_NAME")?;
+    if center_name != "EARTH" {
+        return Err(OemError::Unsupported(center_name.to_string()));
+    }
+    let time_system = get("TIME_SYSTEM")?;
+    if time_system != "UTC" {
+        return Err(OemError::Unsupported(time_system.to_string()));
+    }
+    if times.len() < 2 {
+        return Err(OemError::Invalid("fewer than two states".to_string()));
+    }
+    if let Some(pair) = times.windows(2).find(|pair| pair[1] <= pair[0]) {
+        return Err(OemError::Invalid(format_epoch(pair[1])?));
+    }
+
+    let ref_frame = parse_frame(get("REF_FRAME")?)?;
+    let (interpolation, order) = parse_interpolation(
+        keywords.get("INTERPOLATION").map(|s| s.as_str()),
+        keywords.get("INTERPOLATION_DEGREE").map(|s| s.as_str()),
+    )?;
+    Ok(OemSegment {
+        metadata: OemMetadata {
+            object_name: get("OBJECT_NAME")?.to_string(),
+            object_id: get("OBJECT_ID")?.to_string(),
+            center_name: center_name.to_string(),
+            ref_frame,
+            time_system: time_system.to_string(),
+            start_time: parse_epoch(get("START_TIME")?)?,
+            stop_time: parse_epoch(get("STOP_TIME")?)?,
+        },
+        ephemeris: Ephemeris::new(
+            times,
+            positions,
+            velocities,
+            ref_frame,
+            interpolation,
+            order,
+        )
+        .map_err(|_| OemError::Invalid("state table".to_string()))?,
+    })
+}
+
+// The seven numbers of a state: epoch, position and velocity. Accelerations
+// that may follow are ignored.
+fn parse_state(fields: &[&str]) -> Result<(f64, Vec3, Vec3), OemError> {
+    if fields.len() < 7 {
+        return Err(OemError::Invalid(fields.join(" ")));
+    }
+    let mut numbers = [0.0; 6];
+    for (number, field) in numbers.iter_mut().zip(&fields[1..7]) {
+        *number = parse_number(field)?;
+    }
+    Ok((
+        parse_epoch(fields[0])?,
+        [numbers[0], numbers[1], numbers[2]],
+        [numbers[3], numbers[4], numbers[5]],
+    ))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn unescape(text: &str) -> String {
+    text.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Contents of every `<tag>` element in `text`, in order.
+fn xml_elements<'a>(text: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        // skip longer tag names sharing the prefix
+        if !after.starts_with(|c: char| c == '>' || c.is_whitespace()) {
+            rest = after;
+            continue;
+        }
+        let Some(body) = after.find('>').map(|i| &after[i + 1..]) else {
+            break;
+        };
+        let Some(end) = body.find(&close) else {
+            break;
+        };
+        elements.push(&body[..end]);
+        rest = &body[end + close.len()..];
+    }
+    elements
+}
+
+fn xml_field(text: &str, tag: &str) -> Result<String, OemError> {
+    xml_elements(text, tag)
+        .first()
+        .map(|value| unescape(value))
+        .ok_or_else(|| OemError::Missing(tag.to_string()))
+}
+
+impl Oem {
+    /// Message with one segment sampling `propagator` every `step` seconds
+    /// from Julian date `start` to `end`, with the states converted to
+    /// `frame`. The segment keeps the error bound of [`Ephemeris::sample`].
+    pub fn sample<P: Propagator + ?Sized>(
+        propagator: &mut P,
+        object_name: &str,
+        object_id: &str,
+        start: f64,
+        end: f64,
+        step: f64,
+        frame: Frame,
+    ) -> Result<Oem, PropagatorError> {
+        let ephemeris = Ephemeris::sample(
+            propagator,
+            start,
+            end,
+            step,
+            Interpolation::Lagrange,
+            DEFAULT_ORDER,
+        )?;
+        Ok(Oem {
+            originator: ORIGINATOR.to_string(),
+            creation_date: jday_date(Utc::now()),
+            segments: vec![OemSegment {
+                metadata: OemMetadata {
+                    object_name: object_name.to_string(),
+                    object_id: object_id.to_string(),
+                    center_name: "EARTH".to_string(),
+                    ref_frame: frame,
+                    time_system: "UTC".to_string(),
+                    start_time: start,
+                    stop_time: end,
+                },
+                ephemeris: ephemeris.to_frame(frame),
+            }],
+        })
+    }
+
+    /// The message in keyword-value notation.
+    pub fn to_kvn(&self) -> Result<String, OemError> {
+        let mut kvn = format!(
+            "CCSDS_OEM_VERS = {}\nCREATION_DATE = {}\nORIGINATOR = {}\n",
+            VERSION,
+            format_epoch(self.creation_date)?,
+            self.originator
+        );
+        for segment in &self.segments {
+            let metadata = &segment.metadata;
+            let (interpolation, degree) = interpolation_keywords(&segment.ephemeris);
+            kvn.push_str(&format!(
+                "\nMETA_START\nOBJECT_NAME = {}\nOBJECT_ID = {}\nCENTER_NAME = {}\n\
+                 REF_FRAME = {}\nTIME_SYSTEM = {}\nSTART_TIME = {}\nSTOP_TIME = {}\n\
+                 INTERPOLATION = {}\nINTERPOLATION_DEGREE = {}\nMETA_STOP\n\n",
+                metadata.object_name,
+                metadata.object_id,
+                metadata.center_name,
+                frame_name(metadata.ref_frame),
+                metadata.time_system,
+                format_epoch(metadata.start_time)?,
+                format_epoch(metadata.stop_time)?,
+                interpolation,
+                degree
+            ));
+            let ephemeris = &segment.ephemeris;
+            for ((t, r), v) in ephemeris
+                .times()
+                .iter()
+                .zip(ephemeris.positions())
+                .zip(ephemeris.velocities())
+            {
+                kvn.push_str(&format!(
+                    "{} {:.6} {:.6} {:.6} {:.9} {:.9} {:.9}\n",
+                    format_epoch(*t)?,
+                    r[0],
+                    r[1],
+                    r[2],
+                    v[0],
+                    v[1],
+                    v[2]
+                ));
+            }
+        }
+        Ok(kvn)
+    }
+
+    /// The message in the XML notation of the Navigation Data Messages.
+    pub fn to_xml(&self) -> Result<String, OemError> {
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <oem xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+             xsi:noNamespaceSchemaLocation=\"https://sanaregistry.org/r/ndmxml_unqualified/ndmxml-2.0.0-master-2.0.xsd\" \
+             id=\"CCSDS_OEM_VERS\" version=\"{}\">\n  <header>\n    \
+             <CREATION_DATE>{}</CREATION_DATE>\n    <ORIGINATOR>{}</ORIGINATOR>\n  \
+             </header>\n  <body>\n",
+            VERSION,
+            format_epoch(self.creation_date)?,
+            escape(&self.originator)
+        );
+        for segment in &self.segments {
+            let metadata = &segment.metadata;
+            let (interpolation, degree) = interpolation_keywords(&segment.ephemeris);
+            xml.push_str("    <segment>\n      <metadata>\n");
+            for (tag, value) in [
+                ("OBJECT_NAME", escape(&metadata.object_name)),
+                ("OBJECT_ID", escape(&metadata.object_id)),
+                ("CENTER_NAME", escape(&metadata.center_name)),
+                ("REF_FRAME", frame_name(metadata.ref_frame).to_string()),
+                ("TIME_SYSTEM", escape(&metadata.time_system)),
+                ("START_TIME", format_epoch(metadata.start_time)?),
+                ("STOP_TIME", format_epoch(metadata.stop_time)?),
+                ("INTERPOLATION", interpolation.to_string()),
+                ("INTERPOLATION_DEGREE", degree.to_string()),
+            ] {
+                xml.push_str(&format!("        <{0}>{1}</{0}>\n", tag, value));
+            }
+            xml.push_str("      </metadata>\n      <data>\n");
+            let ephemeris = &segment.ephemeris;
+            for ((t, r), v) in ephemeris
+                .times()
+                .iter()
+                .zip(ephemeris.positions())
+                .zip(ephemeris.velocities())
+            {
+                xml.push_str(&format!(
+                    "        <stateVector>\n          <EPOCH>{}</EPOCH>\n          \
+                     <X>{:.6}</X>\n          <Y>{:.6}</Y>\n          <Z>{:.6}</Z>\n          \
+                     <X_DOT>{:.9}</X_DOT>\n          <Y_DOT>{:.9}</Y_DOT>\n          \
+                     <Z_DOT>{:.9}</Z_DOT>\n        </stateVector>\n",
+                    format_epoch(*t)?,
+                    r[0],
+                    r[1],
+                    r[2],
+                    v[0],
+                    v[1],
+                    v[2]
+                ));
+            }
+            xml.push_str("      </data>\n    </segment>\n");
+        }
+        xml.push_str("  </body>\n</oem>\n");
+        Ok(xml)
+    }
+
+    /// Read a message in either notation, told apart by its first character.
+    pub fn parse(text: &str) -> Result<Oem, OemError> {
+        if text.trim_start().starts_with('<') {
+            Oem::parse_xml(text)
+        } else {
+            Oem::parse_kvn(text)
+        }
+    }
+
+    /// Read a message in keyword-value notation.
+    pub fn parse_kvn(text: &str) -> Result<Oem, OemError> {
+        let mut header: HashMap<String, String> = HashMap::new();
+        let mut segments = Vec::new();
+        // metadata of the segment being read, and its states
+        let mut keywords: Option<HashMap<String, String>> = None;
+        let mut in_metadata = false;
+        let mut in_covariance = false;
+        let (mut times, mut positions, mut velocities) = (Vec::new(), Vec::new(), Vec::new());
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("COMMENT") {
+                continue;
+            }
+            match line {
+                "META_START" => {
+                    if let Some(keywords) = keywords.take() {
+                        segments.push(build_segment(
+                            &keywords,
+                            std::mem::take(&mut times),
+                            std::mem::take(&mut positions),
+                            std::mem::take(&mut velocities),
+                        )?);
+                    }
+                    keywords = Some(HashMap::new());
+                    in_metadata = true;
+                }
+                "META_STOP" => in_metadata = false,
+                "COVARIANCE_START" => in_covariance = true,
+                "COVARIANCE_STOP" => in_covariance = false,
+                _ if in_covariance => {}
+                _ => {
+                    if let Some((key, value)) = line.split_once('=') {
+                        let (key, value) = (key.trim().to_string(), value.trim().to_string());
+                        match keywords.as_mut() {
+                            Some(keywords) if in_metadata => keywords.insert(key, value),
+                            _ => header.insert(key, value),
+                        };
+                    } else if keywords.is_some() && !in_metadata {
+                        let fields: Vec<&str> = line.split_whitespace().collect();
+                        let (t, r, v) = parse_state(&fields)?;
+                        times.push(t);
+                        positions.push(r);
+                        velocities.push(v);
+                    } else {
+                        return Err(OemError::Invalid(line.to_string()));
+                    }
+                }
+            }
+        }
+        match keywords {
+            Some(keywords) => {
+                segments.push(build_segment(&keywords, times, positions, velocities)?)
+            }
+            None => return Err(OemError::Missing("META_START".to_string())),
+        }
+
+        let header_field = |key: &str| {
+            header
+                .get(key)
+                .cloned()
+                .ok_or_else(|| OemError::Missing(key.to_string()))
+        };
+        header_field("CCSDS_OEM_VERS")?;
+        Ok(Oem {
+            originator: header_field("ORIGINATOR")?,
+            creation_date: parse_epoch(&header_field("CREATION_DATE")?)?,
+            segments,
+        })
+    }
+
+    /// Read a message in XML notation.
+    pub fn parse_xml(text: &str) -> Result<Oem, OemError> {
+        let header = xml_elements(text, "header")
+            .first()
+            .copied()
+            .ok_or_else(|| OemError::Missing("header".to_string()))?;
+        let mut segments = Vec::new();
+        for segment in xml_elements(text, "segment") {
+            let metadata = xml_elements(segment, "metadata")
+                .first()
+                .copied()
+                .ok_or_else(|| OemError::Missing("metadata".to_string()))?;
+            let mut keywords = HashMap::new();
+            for key in [
+                "OBJECT_NAME",
+                "OBJECT_ID",
+                "CENTER_NAME",
+                "REF_FRAME",
+                "TIME_SYSTEM",
+                "START_TIME",
+                "STOP_TIME",
+                "INTERPOLATION",
+                "INTERPOLATION_DEGREE",
+            ] {
+                if let Ok(value) = xml_field(metadata, key) {
+                    keywords.insert(key.to_string(), value);
+                }
+            }
+
+            let (mut times, mut positions, mut velocities) = (Vec::new(), Vec::new(), Vec::new());
+            for state in xml_elements(segment, "stateVector") {
+                let fields = ["EPOCH", "X", "Y", "Z", "X_DOT", "Y_DOT", "Z_DOT"]
+                    .iter()
+                    .map(|tag| xml_field(state, tag))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let fields: Vec<&str> = fields.iter().map(|s| s.as_str()).collect();
+                let (t, r, v) = parse_state(&fields)?;
+                times.push(t);
+                positions.push(r);
+                velocities.push(v);
+            }
+            segments.push(build_segment(&keywords, times, positions, velocities)?);
+        }
+        if segments.is_empty() {
+            return Err(OemError::Missing("segment".to_string()));
+        }
+
+        Ok(Oem {
+            originator: xml_field(header, "ORIGINATOR")?,
+            creation_date: parse_epoch(&xml_field(header, "CREATION_DATE")?)?,
+            segments,
+        })
+    }
+}
+
+#[wasm_bindgen(js_name = "oemKvn")]
+pub fn js_oem_kvn(
+    satrec: &mut SatRec,
+    object_name: &str,
+    object_id: &str,
+    start: f64,
+    end: f64,
+    step: f64,
+    frame: Frame,
+) -> Result<String, JsValue> {
+    let oem = Oem::sample(satrec, object_name, object_id, start, end, step, frame)
+        .map_err(|error| to_value(&error).unwrap())?;
+    oem.to_kvn()
+        .map_err(|oem_error| to_value(&oem_error).unwrap())
+}
+
+#[wasm_bindgen(js_name = "oemXml")]
+pub fn js_oem_xml(
+    satrec: &mut SatRec,
+    object_name: &str,
+    object_id: &str,
+    start: f64,
+    end: f64,
+    step: f64,
+    frame: Frame,
+) -> Result<String, JsValue> {
+    let oem = Oem::sample(satrec, object_name, object_id, start, end, step, frame)
+        .map_err(|error| to_value(&error).unwrap())?;
+    oem.to_xml()
+        .map_err(|oem_error| to_value(&oem_error).unwrap())
+}
+
+/// The ephemerides of the segments of an OEM, in either notation.
+#[wasm_bindgen(js_name = "parseOem")]
+pub fn js_parse_oem(text: &str) -> Result<Vec<Ephemeris>, JsValue> {
+    match Oem::parse(text) {
+        Ok(oem) => Ok(oem.segments.into_iter().map(|s| s.ephemeris).collect()),
+        Err(oem_error) => Err(to_value(&oem_error).unwrap()),
+    }
+}
+
+/// The metadata of the segments of an OEM, in either notation.
+#[wasm_bindgen(js_name = "parseOemMetadata")]
+pub fn js_parse_oem_metadata(text: &str) -> Result<JsValue, JsValue> {
+    match Oem::parse(text) {
+        Ok(oem) => {
+            let metadata: Vec<&OemMetadata> = oem.segments.iter().map(|s| &s.metadata).collect();
+            Ok(to_value(&metadata).unwrap())
+        }
+        Err(oem_error) => Err(to_value(&oem_error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::twoline2satrec;
+    use crate::vector::{norm, sub};
+
+    fn iss_oem(frame: Frame) -> Oem {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let start = satrec.jdsatepoch;
+        Oem::sample(
+            &mut satrec,
+            "ISS (ZARYA)",
+            "1998-067A",
+            start,
+            start + 0.05,
+            60.0,
+            frame,
+        )
+        .unwrap()
+    }
+
+    fn assert_same_states(a: &Ephemeris, b: &Ephemeris) {
+        assert_eq!(a.times().len(), b.times().len());
+        for i in 0..a.times().len() {
+            assert!((a.times()[i] - b.times()[i]).abs() * 86400.0 < 1e-5);
+            assert!(norm(&sub(&a.positions()[i], &b.positions()[i])) < 1e-6);
+            assert!(norm(&sub(&a.velocities()[i], &b.velocities()[i])) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_epochs() {
+        let jd = 2460311.25;
+        assert_eq!(format_epoch(jd).unwrap(), "2024-01-01T18:00:00.000000");
+        assert!(format_epoch(1e12).is_err() && format_epoch(f64::NAN).is_err());
+        assert_eq!(parse_epoch("2024-01-01T18:00:00").unwrap(), jd);
+        assert_eq!(parse_epoch("2024-001T18:00:00.000Z").unwrap(), jd);
+        // a Julian date only resolves some 40 µs
+        let later = parse_epoch("2024-01-01T18:00:00.5").unwrap();
+        assert!(((later - jd) * 86400.0 - 0.5).abs() < 1e-4);
+        assert!(parse_epoch("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_kvn_round_trip() {
+        let oem = iss_oem(Frame::J2000);
+        assert!(oem.segments[0].ephemeris.error_bound().unwrap() < 1e-5);
+        let kvn = oem.to_kvn().unwrap();
+        assert!(kvn.contains("REF_FRAME = EME2000"));
+        assert!(kvn.contains("INTERPOLATION_DEGREE = 7"));
+
+        let parsed = Oem::parse(&kvn).unwrap();
+        assert_eq!(parsed.originator, ORIGINATOR);
+        let metadata = &parsed.segments[0].metadata;
+        assert_eq!(metadata.object_name, "ISS (ZARYA)");
+        assert_eq!(metadata.ref_frame, Frame::J2000);
+        assert_eq!(parsed.segments[0].ephemeris.order(), DEFAULT_ORDER);
+        assert_same_states(&oem.segments[0].ephemeris, &parsed.segments[0].ephemeris);
+    }
+
+    #[test]
+    fn test_xml_round_trip() {
+        let oem = iss_oem(Frame::Ecef);
+        let xml = oem.to_xml().unwrap();
+        assert!(xml.contains("<REF_FRAME>TDR</REF_FRAME>"));
+
+        let parsed = Oem::parse(&xml).unwrap();
+        assert_eq!(parsed.segments[0].metadata.object_id, "1998-067A");
+        assert_eq!(parsed.segments[0].metadata.ref_frame, Frame::Ecef);
+        assert_same_states(&oem.segments[0].ephemeris, &parsed.segments[0].ephemeris);
+    }
+
+    #[test]
+    fn test_parse_kvn_segments() {
+        let kvn = "CCSDS_OEM_VERS = 2.0
+COMMENT written by hand
+CREATION_DATE = 2024-001T00:00:00
+ORIGINATOR = TEST
+
+META_START
+OBJECT_NAME = A
+OBJECT_ID = 2024-001A
+CENTER_NAME = EARTH
+REF_FRAME = TEME
+TIME_SYSTEM = UTC
+START_TIME = 2024-01-01T00:00:00
+STOP_TIME = 2024-01-01T00:02:00
+INTERPOLATION = HERMITE
+INTERPOLATION_DEGREE = 5
+META_STOP
+2024-01-01T00:00:00 7000 0 0 0 7.5 0
+2024-01-01T00:01:00 6996 450 0 -0.48 7.49 0
+2024-01-01T00:02:00 6984 899 0 -0.96 7.44 0
+
+COVARIANCE_START
+EPOCH = 2024-01-01T00:00:00
+1.0
+COVARIANCE_STOP
+
+META_START
+OBJECT_NAME = A
+OBJECT_ID = 2024-001A
+CENTER_NAME = EARTH
+REF_FRAME = ITRF2000
+TIME_SYSTEM = UTC
+START_TIME = 2024-01-01T00:02:00
+STOP_TIME = 2024-01-01T00:03:00
+META_STOP
+2024-01-01T00:02:00 1 2 3 4 5 6 0.1 0.2 0.3
+2024-01-01T00:03:00 1 2 3 4 5 6 0.1 0.2 0.3
+";
+        let oem = Oem::parse(kvn).unwrap();
+        assert_eq!(oem.segments.len(), 2);
+        let first = &oem.segments[0].ephemeris;
+        assert_eq!(first.interpolation(), Interpolation::Hermite);
+        assert_eq!(first.order(), 3);
+        assert_eq!(first.positions()[1], [6996.0, 450.0, 0.0]);
+        assert_eq!(oem.segments[1].metadata.ref_frame, Frame::Ecef);
+        assert_eq!(oem.segments[1].ephemeris.velocities()[0], [4.0, 5.0, 6.0]);
+
+        let moon = kvn.replacen("CENTER_NAME = EARTH", "CENTER_NAME = MOON", 1);
+        assert_eq!(
+            Oem::parse(&moon).unwrap_err(),
+            OemError::Unsupported("MOON".to_string())
+        );
+        let tai = kvn.replacen("TIME_SYSTEM = UTC", "TIME_SYSTEM = TAI", 1);
+        assert_eq!(
+            Oem::parse(&tai).unwrap_err(),
+            OemError::Unsupported("TAI".to_string())
+        );
+        let unnamed = kvn.replacen("OBJECT_NAME = A\n", "", 1);
+        assert_eq!(
+            Oem::parse(&unnamed).unwrap_err(),
+            OemError::Missing("OBJECT_NAME".to_string())
+        );
+        let repeated = kvn.replacen("2024-01-01T00:01:00", "2024-01-01T00:00:00", 1);
+        assert_eq!(
+            Oem::parse(&repeated).unwrap_err(),
+            OemError::Invalid("2024-01-01T00:00:00.000000".to_string())
+        );
+        for degree in ["-1", "3.5", "1000"] {
+            let line = format!("INTERPOLATION_DEGREE = {}", degree);
+            let bad = kvn.replacen("INTERPOLATION_DEGREE = 5", &line, 1);
+            assert_eq!(
+                Oem::parse(&bad).unwrap_err(),
+                OemError::Invalid(degree.to_string())
+            );
+        }
+    }
+}
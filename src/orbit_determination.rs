@@ -0,0 +1,819 @@
+use crate::constants::{GravityModel, DEG2RAD, MINUTES_PER_DAY, TWO_PI};
+use crate::elements::rv2coe;
+use crate::ext::jday;
+use crate::io::twoline2satrec;
+use crate::propagation::sgp4::{sgp4, Sgp4Error};
+use crate::propagation::sgp4init::{sgp4init, Sgp4InitOptions};
+use crate::transforms::{state_to_teme, Frame};
+use crate::vector::{add, cross, dot, norm, scale, Vec3};
+use crate::{DpperOpsMode, SatRec};
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::{from_value, to_value};
+use wasm_bindgen::prelude::*;
+
+/// Seconds by which velocity residuals are multiplied to weigh them against
+/// position residuals: 1 m/s counts as much as 1 km.
+const VELOCITY_WEIGHT: f64 = 1000.0;
+
+/// Relative decrease of the residual sum of squares below which the fit is
+/// considered converged.
+const CONVERGENCE: f64 = 1e-8;
+
+/// Times a rejected correction is halved before the fit gives up.
+const MAX_HALVINGS: usize = 8;
+
+// Julian date, TEME position and, when observed, TEME velocity.
+type Sample = (f64, Vec3, Option<Vec3>);
+
+/// A sampled position, and optionally velocity, of the spacecraft. Vectors
+/// are in km and km/s, in the frame given by [`FitOptions::frame`].
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Observation {
+    /// Julian date.
+    pub jd: f64,
+    pub position: [f64; 3],
+    #[serde(default)]
+    pub velocity: Option<[f64; 3]>,
+}
+
+/// SGP4 mean elements of a fitted element set, ready to be written to a
+/// TLE. Angles are in radians.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeanElements {
+    /// Kozai mean motion, as given in a TLE, in rad/min.
+    pub no_kozai: f64,
+    pub ecco: f64,
+    pub inclo: f64,
+    pub nodeo: f64,
+    pub argpo: f64,
+    pub mo: f64,
+    pub bstar: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FitOptions {
+    /// Frame of the observations.
+    pub frame: Frame,
+    /// Estimate B* along with the elements. Otherwise it is held at `bstar`.
+    pub fit_bstar: bool,
+    /// Initial or fixed value of B*, 1 / Earth radii. Defaults to the value
+    /// of the initial element set, or zero.
+    pub bstar: Option<f64>,
+    pub max_iterations: usize,
+}
+
+impl Default for FitOptions {
+    fn default() -> FitOptions {
+        FitOptions {
+            frame: Frame::Teme,
+            fit_bstar: false,
+            bstar: None,
+            max_iterations: 25,
+        }
+    }
+}
+
+/// How well the fitted element set reproduces the observations.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FitStatistics {
+    /// Root mean square of the position residuals, km.
+    pub rms_position: f64,
+    /// Largest position residual, km.
+    pub max_position: f64,
+    /// Root mean square of the velocity residuals, km/s, when velocities
+    /// were observed.
+    pub rms_velocity: Option<f64>,
+    pub observations: usize,
+    pub iterations: usize,
+    /// Whether the residuals stopped decreasing before `max_iterations`.
+    pub converged: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct TleFit {
+    pub satrec: SatRec,
+    pub elements: MeanElements,
+    pub statistics: FitStatistics,
+    /// Covariance of the estimated parameters, scaled by the variance of
+    /// the residuals. The parameters are the equinoctial elements
+    /// `n, e cos(ω+Ω), e sin(ω+Ω), tan(i/2) sin Ω, tan(i/2) cos Ω, M+ω+Ω`
+    /// (rad/min and radians) followed by B* when it is estimated.
+    pub covariance: Vec<Vec<f64>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum FitError {
+    /// SGP4 failed on the element set being corrected.
+    Sgp4(Sgp4Error),
+    /// Fewer residuals than estimated parameters, or too few samples to
+    /// build an initial guess.
+    TooFewObservations,
+    /// The normal equations cannot be solved: the observations do not
+    /// determine the parameters.
+    Singular,
+    /// The span to sample for a re-epoch is not positive.
+    InvalidSpan,
+}
+
+impl From<Sgp4Error> for FitError {
+    fn from(error: Sgp4Error) -> FitError {
+        FitError::Sgp4(error)
+    }
+}
+
+// Two-digit year and day of year of a Julian date, as stored in a TLE.
+fn epoch_fields(jd: f64) -> (u32, f64) {
+    let new_year = |year: i32| jday(year as f64, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0);
+    let mut year = 2000 + ((jd - 2451544.5) / 365.25).floor() as i32;
+    while new_year(year) > jd {
+        year -= 1;
+    }
+    while new_year(year + 1) <= jd {
+        year += 1;
+    }
+    ((year % 100) as u32, jd - new_year(year) + 1.0)
+}
+
+/// Initialise SGP4 for `elements` at the Julian date `epoch`.
+pub(crate) fn satrec_from_elements(satnum: &str, epoch: f64, elements: &MeanElements) -> SatRec {
+    let mut satrec = SatRec::new();
+    satrec.satnum = String::from(satnum);
+    let (epochyr, epochdays) = epoch_fields(epoch);
+    satrec.epochyr = epochyr;
+    satrec.epochdays = epochdays;
+    satrec.jdsatepoch = epoch;
+    sgp4init(
+        &mut satrec,
+        Sgp4InitOptions {
+            opsmode: DpperOpsMode::I,
+            satn: satnum.parse::<f64>().unwrap_or(0.0),
+            epoch: epoch - 2433281.5,
+            xbstar: elements.bstar,
+            xecco: elements.ecco,
+            xargpo: elements.argpo,
+            xinclo: elements.inclo,
+            xmo: elements.mo,
+            xno: elements.no_kozai,
+            xnodeo: elements.nodeo,
+        },
+    );
+    satrec
+}
+
+/// Mean elements of `satrec` advanced to `epoch` along their secular rates.
+/// The mean motion is the Brouwer value SGP4 keeps, a close guess of the
+/// Kozai one.
+pub(crate) fn advanced_elements(satrec: &SatRec, epoch: f64) -> MeanElements {
+    let dt = (epoch - satrec.jdsatepoch) * MINUTES_PER_DAY;
+    MeanElements {
+        no_kozai: satrec.no,
+        ecco: satrec.ecco,
+        inclo: satrec.inclo,
+        nodeo: (satrec.nodeo + satrec.nodedot * dt).rem_euclid(TWO_PI),
+        argpo: (satrec.argpo + satrec.argpdot * dt).rem_euclid(TWO_PI),
+        mo: (satrec.mo + satrec.mdot * dt).rem_euclid(TWO_PI),
+        bstar: satrec.bstar,
+    }
+}
+
+fn to_parameters(elements: &MeanElements, fit_bstar: bool) -> Vec<f64> {
+    let lonper = elements.argpo + elements.nodeo;
+    let tan_half = (0.5 * elements.inclo).tan();
+    let mut x = vec![
+        elements.no_kozai,
+        elements.ecco * lonper.cos(),
+        elements.ecco * lonper.sin(),
+        tan_half * elements.nodeo.sin(),
+        tan_half * elements.nodeo.cos(),
+        elements.mo + lonper,
+    ];
+    if fit_bstar {
+        x.push(elements.bstar);
+    }
+    x
+}
+
+fn from_parameters(x: &[f64], bstar: f64) -> MeanElements {
+    let ecco = x[1].hypot(x[2]).min(0.999);
+    let lonper = x[2].atan2(x[1]);
+    let nodeo = x[3].atan2(x[4]);
+    MeanElements {
+        no_kozai: x[0],
+        ecco,
+        inclo: 2.0 * x[3].hypot(x[4]).atan(),
+        nodeo: nodeo.rem_euclid(TWO_PI),
+        argpo: (lonper - nodeo).rem_euclid(TWO_PI),
+        mo: (x[5] - lonper).rem_euclid(TWO_PI),
+        bstar: x.get(6).copied().unwrap_or(bstar),
+    }
+}
+
+// Velocity at the middle of three position samples: Gibbs' method, or the
+// Herrick-Gibbs expansion when the positions are less than a degree apart
+// (Vallado, algorithms 54 and 55).
+fn gibbs(t: [f64; 3], r: [Vec3; 3]) -> Vec3 {
+    let mu = GravityModel::Wgs72.mu();
+    let [r1, r2, r3] = r;
+    let (m1, m2, m3) = (norm(&r1), norm(&r2), norm(&r3));
+    let angle =
+        |a: &Vec3, b: &Vec3, ma: f64, mb: f64| (dot(a, b) / (ma * mb)).clamp(-1.0, 1.0).acos();
+
+    if angle(&r1, &r2, m1, m2) < 1.0 * DEG2RAD && angle(&r2, &r3, m2, m3) < 1.0 * DEG2RAD {
+        let dt21 = (t[1] - t[0]) * 86400.0;
+        let dt31 = (t[2] - t[0]) * 86400.0;
+        let dt32 = (t[2] - t[1]) * 86400.0;
+        let c1 = -dt32 * (1.0 / (dt21 * dt31) + mu / (12.0 * m1.powi(3)));
+        let c2 = (dt32 - dt21) * (1.0 / (dt21 * dt32) + mu / (12.0 * m2.powi(3)));
+        let c3 = dt21 * (1.0 / (dt32 * dt31) + mu / (12.0 * m3.powi(3)));
+        return add(&add(&scale(&r1, c1), &scale(&r2, c2)), &scale(&r3, c3));
+    }
+
+    let z12 = cross(&r1, &r2);
+    let z23 = cross(&r2, &r3);
+    let z31 = cross(&r3, &r1);
+    let n = add(&add(&scale(&z23, m1), &scale(&z31, m2)), &scale(&z12, m3));
+    let d = add(&add(&z12, &z23), &z31);
+    let s = add(
+        &add(&scale(&r1, m2 - m3), &scale(&r2, m3 - m1)),
+        &scale(&r3, m1 - m2),
+    );
+    let b = cross(&d, &r2);
+    let lg = (mu / (norm(&n) * norm(&d))).sqrt();
+    add(&scale(&b, lg / m2), &scale(&s, lg))
+}
+
+// Starting elements from the osculating state of one observation. When no
+// velocity was observed it is estimated from the first three positions.
+fn initial_elements(observations: &[Sample], epoch: f64) -> Option<MeanElements> {
+    let (jd, position, velocity) = match observations.iter().find(|o| o.2.is_some()) {
+        Some(&(jd, position, velocity)) => (jd, position, velocity?),
+        None if observations.len() >= 3 => {
+            let (t0, r0, _) = observations[0];
+            let (t1, r1, _) = observations[1];
+            let (t2, r2, _) = observations[2];
+            (t1, r1, gibbs([t0, t1, t2], [r0, r1, r2]))
+        }
+        None => return None,
+    };
+
+    let elements = rv2coe(&position.into(), &velocity.into(), GravityModel::Wgs72);
+    if elements.ecc.is_nan() || elements.ecc >= 1.0 {
+        return None;
+    }
+    let no = (GravityModel::Wgs72.mu() / elements.a.powi(3)).sqrt() * 60.0;
+    // circular and equatorial orbits report their phase in other angles
+    let argp = if elements.argp.is_nan() {
+        0.0
+    } else {
+        elements.argp
+    };
+    let raan = if elements.raan.is_nan() {
+        0.0
+    } else {
+        elements.raan
+    };
+    let mo = if !elements.m.is_nan() && !elements.argp.is_nan() {
+        elements.m
+    } else if !elements.arglat.is_nan() {
+        elements.arglat
+    } else if !elements.lonper.is_nan() {
+        elements.lonper - argp - raan + elements.m
+    } else {
+        elements.truelon - argp - raan
+    };
+    Some(MeanElements {
+        no_kozai: no,
+        ecco: elements.ecc,
+        inclo: elements.incl,
+        nodeo: raan,
+        argpo: argp,
+        mo: (mo + no * (epoch - jd) * MINUTES_PER_DAY).rem_euclid(TWO_PI),
+        bstar: 0.0,
+    })
+}
+
+// Model values matching the layout of the observation vector: three
+// position components per sample, followed by three weighted velocity
+// components when the velocity was observed.
+fn model(satrec: &mut SatRec, observations: &[Sample]) -> Result<Vec<f64>, Sgp4Error> {
+    let mut values = Vec::new();
+    for (jd, _, velocity) in observations {
+        let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+        let r: Vec3 = result.position().into();
+        values.extend_from_slice(&r);
+        if velocity.is_some() {
+            let v: Vec3 = result.velocity().into();
+            values.extend_from_slice(&scale(&v, VELOCITY_WEIGHT));
+        }
+    }
+    Ok(values)
+}
+
+fn observed(observations: &[Sample]) -> Vec<f64> {
+    let mut values = Vec::new();
+    for (_, position, velocity) in observations {
+        values.extend_from_slice(position);
+        if let Some(velocity) = velocity {
+            values.extend_from_slice(&scale(velocity, VELOCITY_WEIGHT));
+        }
+    }
+    values
+}
+
+fn residuals(
+    satnum: &str,
+    epoch: f64,
+    x: &[f64],
+    bstar: f64,
+    observations: &[Sample],
+    y: &[f64],
+) -> Result<Vec<f64>, Sgp4Error> {
+    let mut satrec = satrec_from_elements(satnum, epoch, &from_parameters(x, bstar));
+    let values = model(&mut satrec, observations)?;
+    Ok(y.iter().zip(values).map(|(o, c)| o - c).collect())
+}
+
+// Inverse of a symmetric positive definite matrix by Gauss-Jordan
+// elimination with partial pivoting, after scaling it to a unit diagonal.
+fn invert(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let d: Vec<f64> = (0..n).map(|i| 1.0 / matrix[i][i].sqrt()).collect();
+    if d.iter().any(|value| !value.is_finite()) {
+        return None;
+    }
+    let mut a: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| matrix[i][j] * d[i] * d[j]).collect())
+        .collect();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+
+    for column in 0..n {
+        let pivot =
+            (column..n).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs()))?;
+        if a[pivot][column].abs() < 1e-14 {
+            return None;
+        }
+        a.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let p = a[column][column];
+        for j in 0..n {
+            a[column][j] /= p;
+            inverse[column][j] /= p;
+        }
+        for i in 0..n {
+            if i != column {
+                let f = a[i][column];
+                for j in 0..n {
+                    a[i][j] -= f * a[column][j];
+                    inverse[i][j] -= f * inverse[column][j];
+                }
+            }
+        }
+    }
+
+    Some(
+        (0..n)
+            .map(|i| (0..n).map(|j| inverse[i][j] * d[i] * d[j]).collect())
+            .collect(),
+    )
+}
+
+// Normal matrix and right-hand side of the linearised least-squares
+// problem, with the Jacobian of the model taken by central differences.
+fn normal_equations(
+    satnum: &str,
+    epoch: f64,
+    x: &[f64],
+    bstar: f64,
+    observations: &[Sample],
+    residual: &[f64],
+) -> Result<(Vec<Vec<f64>>, Vec<f64>), Sgp4Error> {
+    let n = x.len();
+    let mut columns = Vec::with_capacity(n);
+    for k in 0..n {
+        let h = if k == 0 { 1e-6 * x[0].abs() } else { 1e-6 };
+        let mut plus = x.to_vec();
+        let mut minus = x.to_vec();
+        plus[k] += h;
+        minus[k] -= h;
+        let up = model(
+            &mut satrec_from_elements(satnum, epoch, &from_parameters(&plus, bstar)),
+            observations,
+        )?;
+        let down = model(
+            &mut satrec_from_elements(satnum, epoch, &from_parameters(&minus, bstar)),
+            observations,
+        )?;
+        columns.push(
+            up.iter()
+                .zip(down)
+                .map(|(u, d)| (u - d) / (2.0 * h))
+                .collect::<Vec<f64>>(),
+        );
+    }
+
+    let normal = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| columns[i].iter().zip(&columns[j]).map(|(a, b)| a * b).sum())
+                .collect()
+        })
+        .collect();
+    let rhs = (0..n)
+        .map(|i| columns[i].iter().zip(residual).map(|(a, r)| a * r).sum())
+        .collect();
+    Ok((normal, rhs))
+}
+
+fn statistics(
+    residual: &[f64],
+    observations: &[Sample],
+    iterations: usize,
+    converged: bool,
+) -> FitStatistics {
+    let mut position_sum = 0.0;
+    let mut max_position: f64 = 0.0;
+    let mut velocity_sum = 0.0;
+    let mut velocities = 0;
+    let mut i = 0;
+    for (_, _, velocity) in observations {
+        let dr = norm(&[residual[i], residual[i + 1], residual[i + 2]]);
+        position_sum += dr * dr;
+        max_position = max_position.max(dr);
+        i += 3;
+        if velocity.is_some() {
+            let dv = norm(&[residual[i], residual[i + 1], residual[i + 2]]) / VELOCITY_WEIGHT;
+            velocity_sum += dv * dv;
+            velocities += 1;
+            i += 3;
+        }
+    }
+    FitStatistics {
+        rms_position: (position_sum / observations.len() as f64).sqrt(),
+        max_position,
+        rms_velocity: (velocities > 0).then(|| (velocity_sum / velocities as f64).sqrt()),
+        observations: observations.len(),
+        iterations,
+        converged,
+    }
+}
+
+fn sum_of_squares(residual: &[f64]) -> f64 {
+    residual.iter().map(|r| r * r).sum()
+}
+
+/// Fit SGP4 mean elements at the Julian date `epoch` to `observations` by
+/// batch least-squares differential correction.
+///
+/// The elements are corrected in equinoctial form, which stays regular for
+/// circular and equatorial orbits, with a Jacobian taken by finite
+/// differences through `sgp4`. Corrections that increase the residuals are
+/// halved. Velocity residuals weigh 1000 s times their position
+/// counterparts.
+///
+/// The starting point is `initial` advanced to `epoch` when given, otherwise
+/// the osculating elements of the first sample with a velocity, or of the
+/// second sample with its velocity differenced from its neighbours.
+pub fn fit_tle(
+    observations: &[Observation],
+    epoch: f64,
+    satnum: &str,
+    initial: Option<&SatRec>,
+    options: &FitOptions,
+) -> Result<TleFit, FitError> {
+    let observations: Vec<Sample> = observations
+        .iter()
+        .map(|o| {
+            let velocity = o.velocity.unwrap_or([0.0; 3]);
+            let (position, velocity) = state_to_teme(options.frame, o.jd, &o.position, &velocity);
+            (o.jd, position, o.velocity.map(|_| velocity))
+        })
+        .collect();
+    let y = observed(&observations);
+    let parameters = if options.fit_bstar { 7 } else { 6 };
+    if y.len() <= parameters {
+        return Err(FitError::TooFewObservations);
+    }
+
+    let mut elements = match initial {
+        Some(satrec) => advanced_elements(satrec, epoch),
+        None => initial_elements(&observations, epoch).ok_or(FitError::TooFewObservations)?,
+    };
+    if let Some(bstar) = options.bstar {
+        elements.bstar = bstar;
+    }
+    let bstar = elements.bstar;
+
+    let mut x = to_parameters(&elements, options.fit_bstar);
+    let mut residual = residuals(satnum, epoch, &x, bstar, &observations, &y)?;
+    let mut cost = sum_of_squares(&residual);
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < options.max_iterations {
+        iterations += 1;
+        let (normal, rhs) = normal_equations(satnum, epoch, &x, bstar, &observations, &residual)?;
+        let inverse = invert(&normal).ok_or(FitError::Singular)?;
+        let dx: Vec<f64> = inverse
+            .iter()
+            .map(|row| row.iter().zip(&rhs).map(|(a, b)| a * b).sum())
+            .collect();
+
+        let mut step = 1.0;
+        let mut accepted = None;
+        for _ in 0..=MAX_HALVINGS {
+            let trial: Vec<f64> = x.iter().zip(&dx).map(|(a, d)| a + step * d).collect();
+            if let Ok(trial_residual) = residuals(satnum, epoch, &trial, bstar, &observations, &y) {
+                let trial_cost = sum_of_squares(&trial_residual);
+                if trial_cost <= cost {
+                    accepted = Some((trial, trial_residual, trial_cost));
+                    break;
+                }
+            }
+            step *= 0.5;
+        }
+
+        match accepted {
+            Some((trial, trial_residual, trial_cost)) => {
+                let improvement = (cost - trial_cost) / cost.max(f64::MIN_POSITIVE);
+                x = trial;
+                residual = trial_residual;
+                cost = trial_cost;
+                if improvement < CONVERGENCE {
+                    converged = true;
+                    break;
+                }
+            }
+            None => {
+                // no correction helps any more: at the minimum to within
+                // the precision of the differences
+                converged = true;
+                break;
+            }
+        }
+    }
+
+    let (normal, _) = normal_equations(satnum, epoch, &x, bstar, &observations, &residual)?;
+    let variance = cost / (y.len() - parameters) as f64;
+    let covariance = invert(&normal)
+        .ok_or(FitError::Singular)?
+        .into_iter()
+        .map(|row| row.into_iter().map(|value| value * variance).collect())
+        .collect();
+
+    let elements = from_parameters(&x, bstar);
+    Ok(TleFit {
+        satrec: satrec_from_elements(satnum, epoch, &elements),
+        elements,
+        statistics: statistics(&residual, &observations, iterations, converged),
+        covariance,
+    })
+}
+
+/// Equivalent element set for `satrec` at the Julian date `epoch`.
+///
+/// The original is sampled every twentieth of an orbit over `span` days
+/// centred on the new epoch, and the mean elements are refitted to those
+/// positions and velocities starting from the original elements advanced
+/// along their secular rates. B* is kept. The fit statistics measure how
+/// far the new element set departs from the original over the span.
+pub fn re_epoch(satrec: &mut SatRec, epoch: f64, span: f64) -> Result<TleFit, FitError> {
+    if span <= 0.0 {
+        return Err(FitError::InvalidSpan);
+    }
+    let step = TWO_PI / satrec.no / 20.0 / MINUTES_PER_DAY;
+    let n = (span / step).ceil().max(20.0) as usize;
+    let mut observations = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        let jd = epoch + span * (i as f64 / n as f64 - 0.5);
+        let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+        observations.push(Observation {
+            jd,
+            position: result.position().into(),
+            velocity: Some(result.velocity().into()),
+        });
+    }
+
+    let mut fit = fit_tle(
+        &observations,
+        epoch,
+        &satrec.satnum(),
+        Some(satrec),
+        &FitOptions::default(),
+    )?;
+    fit.satrec.ndot = satrec.ndot;
+    fit.satrec.nddot = satrec.nddot;
+    fit.satrec
+        .set_intrinsic_magnitude(satrec.intrinsic_magnitude());
+    Ok(fit)
+}
+
+/// Result of [`js_fit_tle`] and [`js_re_epoch`]: the fitted record, its elements, fit
+/// statistics and covariance.
+#[wasm_bindgen]
+pub struct JsTleFit {
+    fit: TleFit,
+}
+
+#[wasm_bindgen]
+impl JsTleFit {
+    #[wasm_bindgen(getter)]
+    pub fn satrec(&self) -> SatRec {
+        self.fit.satrec.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn elements(&self) -> JsValue {
+        to_value(&self.fit.elements).unwrap()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn statistics(&self) -> JsValue {
+        to_value(&self.fit.statistics).unwrap()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn covariance(&self) -> JsValue {
+        to_value(&self.fit.covariance).unwrap()
+    }
+}
+
+/// [`fit_tle`] from JavaScript. The optional initial guess `initial` is a
+/// `[line1, line2]` pair.
+#[wasm_bindgen(js_name = "fitTle")]
+pub fn js_fit_tle(
+    observations: JsValue,
+    epoch: f64,
+    satnum: &str,
+    initial: JsValue,
+    options: JsValue,
+) -> Result<JsTleFit, JsValue> {
+    let observations: Vec<Observation> = from_value(observations)?;
+    let initial = if initial.is_undefined() || initial.is_null() {
+        None
+    } else {
+        let [line1, line2]: [String; 2] = from_value(initial)?;
+        Some(twoline2satrec(&line1, &line2))
+    };
+    let options: FitOptions = if options.is_undefined() || options.is_null() {
+        FitOptions::default()
+    } else {
+        from_value(options)?
+    };
+    fit_tle(&observations, epoch, satnum, initial.as_ref(), &options)
+        .map(|fit| JsTleFit { fit })
+        .map_err(|fit_error| to_value(&fit_error).unwrap())
+}
+
+#[wasm_bindgen(js_name = "reEpoch")]
+pub fn js_re_epoch(satrec: &mut SatRec, epoch: f64, span: f64) -> Result<JsTleFit, JsValue> {
+    re_epoch(satrec, epoch, span)
+        .map(|fit| JsTleFit { fit })
+        .map_err(|fit_error| to_value(&fit_error).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::twoline2satrec;
+    use crate::propagation::gstime::gstime;
+    use crate::transforms::eci_to_ecf;
+
+    fn iss() -> SatRec {
+        twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        )
+    }
+
+    #[test]
+    fn test_epoch_fields() {
+        let jd = jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+        let (year, days) = epoch_fields(jd);
+        assert_eq!(year, 24);
+        assert!((days - 1.5).abs() < 1e-9);
+        let (year, days) = epoch_fields(jday(1999.0, 12.0, 31.0, 0.0, 0.0, 0.0, 0.0));
+        assert_eq!(year, 99);
+        assert!((days - 365.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_gibbs() {
+        let mut truth = iss();
+        for step in [0.5, 15.0] {
+            let mut t = [0.0; 3];
+            let mut r = [[0.0; 3]; 3];
+            let mut v = [0.0; 3];
+            for i in 0..3 {
+                let result = sgp4(&mut truth, step * i as f64).unwrap();
+                t[i] = truth.jdsatepoch + step * i as f64 / MINUTES_PER_DAY;
+                r[i] = result.position().into();
+                if i == 1 {
+                    v = result.velocity().into();
+                }
+            }
+            // SGP4 is not Keplerian, so only a close guess is expected
+            let estimate = gibbs(t, r);
+            assert!(norm(&crate::vector::sub(&estimate, &v)) < 0.02);
+        }
+    }
+
+    #[test]
+    fn test_fit_positions_without_initial_guess() {
+        let mut truth = iss();
+        let epoch = truth.jdsatepoch;
+        let observations: Vec<Observation> = (0..144)
+            .map(|i| {
+                let jd = epoch + i as f64 * 10.0 / MINUTES_PER_DAY;
+                let result = sgp4(&mut truth, (jd - epoch) * MINUTES_PER_DAY).unwrap();
+                Observation {
+                    jd,
+                    position: result.position().into(),
+                    velocity: None,
+                }
+            })
+            .collect();
+        let options = FitOptions {
+            bstar: Some(truth.bstar),
+            ..FitOptions::default()
+        };
+
+        let fit = fit_tle(&observations, epoch, "25544", None, &options).unwrap();
+
+        assert!(fit.statistics.converged);
+        assert!(fit.statistics.rms_position < 1e-3);
+        assert!(fit.statistics.rms_velocity.is_none());
+        assert!((fit.satrec.inclo - truth.inclo).abs() < 1e-7);
+        assert!((fit.satrec.ecco - truth.ecco).abs() < 1e-7);
+        assert!((fit.satrec.no - truth.no).abs() < 1e-9);
+        assert_eq!(fit.covariance.len(), 6);
+        assert_eq!(fit.satrec.satnum(), "25544");
+        assert_eq!(fit.satrec.epochyr, 24);
+    }
+
+    #[test]
+    fn test_fit_ecef_states_with_bstar() {
+        let mut truth = iss();
+        let epoch = truth.jdsatepoch;
+        let observations: Vec<Observation> = (0..96)
+            .map(|i| {
+                let jd = epoch + i as f64 * 30.0 / MINUTES_PER_DAY;
+                let result = sgp4(&mut truth, (jd - epoch) * MINUTES_PER_DAY).unwrap();
+                let gmst = gstime(jd);
+                let position = eci_to_ecf(result.position(), gmst);
+                // Earth-fixed velocity: rotate, then remove the frame rotation
+                let v = eci_to_ecf(result.velocity(), gmst);
+                let omega = crate::constants::OMEGA_EARTH;
+                Observation {
+                    jd,
+                    position: [position.x, position.y, position.z],
+                    velocity: Some([v.x + omega * position.y, v.y - omega * position.x, v.z]),
+                }
+            })
+            .collect();
+        let options = FitOptions {
+            frame: Frame::Ecef,
+            fit_bstar: true,
+            ..FitOptions::default()
+        };
+
+        let fit = fit_tle(&observations, epoch, "25544", None, &options).unwrap();
+
+        assert!(fit.statistics.rms_position < 1e-2);
+        assert!(fit.statistics.rms_velocity.unwrap() < 1e-5);
+        assert!((fit.satrec.bstar / truth.bstar - 1.0).abs() < 1e-3);
+        assert_eq!(fit.covariance.len(), 7);
+        assert!(fit.covariance[6][6] > 0.0);
+    }
+
+    #[test]
+    fn test_re_epoch() {
+        let mut original = iss();
+        original.set_intrinsic_magnitude(Some(-1.8));
+        let epoch = original.jdsatepoch + 2.0;
+
+        let fit = re_epoch(&mut original, epoch, 1.0).unwrap();
+        let mut moved = fit.satrec;
+
+        assert!(fit.statistics.converged);
+        assert!(fit.statistics.rms_position < 0.01);
+        assert_eq!(moved.jdsatepoch, epoch);
+        assert!((moved.epochdays - 3.5).abs() < 1e-9);
+        assert_eq!(moved.bstar, original.bstar);
+        assert_eq!(moved.intrinsic_magnitude(), Some(-1.8));
+
+        let before = sgp4(&mut original, 2.0 * MINUTES_PER_DAY).unwrap();
+        let after = sgp4(&mut moved, 0.0).unwrap();
+        let dr = crate::vector::sub(&before.position().into(), &after.position().into());
+        assert!(norm(&dr) < fit.statistics.max_position + 1e-9);
+
+        for span in [0.0, -1.0] {
+            let error = re_epoch(&mut original, epoch, span).err();
+            assert_eq!(error, Some(FitError::InvalidSpan));
+        }
+    }
+}
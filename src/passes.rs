@@ -0,0 +1,240 @@
+use crate::constants::MINUTES_PER_DAY;
+use crate::propagator::{check_range, ecf_position, Propagator, PropagatorError};
+use crate::search::{find_maximum, find_root, TIME_TOLERANCE};
+use crate::transforms::ecf_to_look_angles;
+use crate::{GeodeticLocation, LookAngles, SatRec};
+use serde::Serialize;
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Coarse sampling step, in minutes, used to bracket passes. It stays below
+/// a fortieth of the period of any Earth orbit.
+const STEP_MINUTES: f64 = 2.0;
+
+/// A pass of a satellite over a ground station. Times are Julian dates,
+/// angles are in radians.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pass {
+    /// Acquisition of signal: the satellite rises above the minimum elevation.
+    /// Clipped to the start of the search window.
+    pub aos: f64,
+    /// Time of closest approach, where the elevation peaks.
+    pub tca: f64,
+    /// Loss of signal: the satellite sets below the minimum elevation.
+    /// Clipped to the end of the search window.
+    pub los: f64,
+    pub max_elevation: f64,
+    pub aos_azimuth: f64,
+    pub tca_azimuth: f64,
+    pub los_azimuth: f64,
+}
+
+impl Pass {
+    /// Duration of the pass in seconds.
+    pub fn duration(&self) -> f64 {
+        (self.los - self.aos) * 86400.0
+    }
+}
+
+/// Look angles from `observer` to the satellite at Julian date `jd`.
+pub fn look_angles_at<P: Propagator + ?Sized>(
+    satrec: &mut P,
+    observer: &GeodeticLocation,
+    jd: f64,
+) -> Result<LookAngles, PropagatorError> {
+    let satellite_ecf = ecf_position(satrec, jd)?;
+    Ok(ecf_to_look_angles(observer, &satellite_ecf))
+}
+
+/// Find the passes of `satrec` over `observer` between Julian dates `start`
+/// and `end` that rise above `min_elevation` (radians). Any [`Propagator`]
+/// can stand in for the SGP4 element set.
+///
+/// The elevation is sampled every two minutes to bracket each local
+/// maximum, which is then refined by golden-section search; rise and set
+/// times are located by bisection to about 10 ms.
+pub fn find_passes<P: Propagator + ?Sized>(
+    satrec: &mut P,
+    observer: &GeodeticLocation,
+    start: f64,
+    end: f64,
+    min_elevation: f64,
+) -> Result<Vec<Pass>, PropagatorError> {
+    let mut passes: Vec<Pass> = Vec::new();
+    if end <= start {
+        return Ok(passes);
+    }
+    check_range(satrec, start, end)?;
+
+    let step = STEP_MINUTES / MINUTES_PER_DAY;
+    let n = ((end - start) / step).ceil().max(1.0) as usize;
+    let times: Vec<f64> = (0..=n)
+        .map(|i| start + (end - start) * (i as f64) / (n as f64))
+        .collect();
+    let mut elevations = Vec::with_capacity(times.len());
+    for &t in &times {
+        elevations.push(look_angles_at(satrec, observer, t)?.elevation());
+    }
+
+    let mut elevation_at = |t: f64| -> Result<f64, PropagatorError> {
+        Ok(look_angles_at(satrec, observer, t)?.elevation())
+    };
+
+    for i in 0..=n {
+        let rising = i == 0 || elevations[i - 1] < elevations[i];
+        let falling = i == n || elevations[i] >= elevations[i + 1];
+        if !(rising && falling) {
+            continue;
+        }
+
+        let (tca, max_elevation) = if i == 0 || i == n {
+            (times[i], elevations[i])
+        } else {
+            find_maximum(
+                &mut elevation_at,
+                times[i - 1],
+                times[i + 1],
+                TIME_TOLERANCE,
+            )?
+        };
+        if max_elevation < min_elevation {
+            continue;
+        }
+
+        if let Some(last) = passes.last_mut() {
+            if tca <= last.los {
+                // a second peak inside the same pass
+                if max_elevation > last.max_elevation {
+                    last.tca = tca;
+                    last.max_elevation = max_elevation;
+                }
+                continue;
+            }
+        }
+
+        let mut aos = start;
+        let mut upper = tca;
+        for k in (0..i).rev() {
+            if elevations[k] < min_elevation {
+                aos = find_root(
+                    |t| elevation_at(t).map(|el| el - min_elevation),
+                    times[k],
+                    upper,
+                    TIME_TOLERANCE,
+                )?;
+                break;
+            }
+            upper = times[k];
+        }
+
+        let mut los = end;
+        let mut lower = tca;
+        for k in (i + 1)..=n {
+            if elevations[k] < min_elevation {
+                los = find_root(
+                    |t| elevation_at(t).map(|el| el - min_elevation),
+                    lower,
+                    times[k],
+                    TIME_TOLERANCE,
+                )?;
+                break;
+            }
+            lower = times[k];
+        }
+
+        passes.push(Pass {
+            aos,
+            tca,
+            los,
+            max_elevation,
+            aos_azimuth: 0.0,
+            tca_azimuth: 0.0,
+            los_azimuth: 0.0,
+        });
+    }
+
+    for pass in passes.iter_mut() {
+        pass.aos_azimuth = look_angles_at(satrec, observer, pass.aos)?.azimuth();
+        pass.tca_azimuth = look_angles_at(satrec, observer, pass.tca)?.azimuth();
+        pass.los_azimuth = look_angles_at(satrec, observer, pass.los)?.azimuth();
+    }
+
+    Ok(passes)
+}
+
+#[wasm_bindgen(js_name = "findPasses")]
+pub fn js_find_passes(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    start: f64,
+    end: f64,
+    min_elevation: f64,
+) -> Result<JsValue, JsValue> {
+    match find_passes(satrec, observer, start, end, min_elevation) {
+        Ok(passes) => Ok(to_value(&passes).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::DEG2RAD;
+    use crate::ext::jday;
+    use crate::io::twoline2satrec;
+
+    #[test]
+    fn test_find_passes_iss() {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let observer = GeodeticLocation::from_degrees(-0.1276, 51.5072, 0.0);
+        let start = jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+        let end = start + 1.0;
+        let min_elevation = 10.0 * DEG2RAD;
+
+        let passes = find_passes(&mut satrec, &observer, start, end, min_elevation).unwrap();
+
+        // the ISS makes several passes a day over mid-latitude stations
+        assert!(!passes.is_empty() && passes.len() < 8);
+        for pass in &passes {
+            assert!(pass.aos < pass.tca && pass.tca < pass.los);
+            assert!(pass.max_elevation >= min_elevation);
+            assert!(pass.duration() > 0.0 && pass.duration() < 15.0 * 60.0);
+            let aos = look_angles_at(&mut satrec, &observer, pass.aos).unwrap();
+            let los = look_angles_at(&mut satrec, &observer, pass.los).unwrap();
+            assert!((aos.elevation() - min_elevation).abs() < 1e-4);
+            assert!((los.elevation() - min_elevation).abs() < 1e-4);
+            for offset in [-10.0, 10.0] {
+                let t = pass.tca + offset / 86400.0;
+                let el = look_angles_at(&mut satrec, &observer, t)
+                    .unwrap()
+                    .elevation();
+                assert!(el <= pass.max_elevation);
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_passes_kepler() {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let observer = GeodeticLocation::from_degrees(-0.1276, 51.5072, 0.0);
+        let start = jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+        let end = start + 0.5;
+        let mut kepler = crate::KeplerPropagator::from_satrec(&mut satrec, start).unwrap();
+
+        let sgp4_passes = find_passes(&mut satrec, &observer, start, end, 0.0).unwrap();
+        let kepler_passes = find_passes(&mut kepler, &observer, start, end, 0.0).unwrap();
+
+        // without J2 the node drifts, shifting the passes by up to a minute or two
+        assert_eq!(sgp4_passes.len(), kepler_passes.len());
+        for (a, b) in sgp4_passes.iter().zip(&kepler_passes) {
+            assert!((a.tca - b.tca).abs() * 86400.0 < 120.0);
+        }
+    }
+}
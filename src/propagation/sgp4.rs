@@ -13,7 +13,7 @@ use crate::EciVec3;
 
 
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum Sgp4Error {
     FF,
     FpFv,
@@ -24,14 +24,24 @@ impl Into<JsValue> for Sgp4Error {
         to_value(&self).unwrap()
     }
 }
-#[allow(dead_code)]
-
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct Sgp4Result {
     position: EciVec3,
     velocity: EciVec3,
 }
 
+impl Sgp4Result {
+    /// Position in the TEME frame, km.
+    pub fn position(&self) -> &EciVec3 {
+        &self.position
+    }
+
+    /// Velocity in the TEME frame, km/s.
+    pub fn velocity(&self) -> &EciVec3 {
+        &self.velocity
+    }
+}
+
 /*----------------------------------------------------------------------------
 *
 *                             procedure sgp4
@@ -0,0 +1,469 @@
+use crate::constants::{GravityModel, MINUTES_PER_DAY};
+use crate::propagation::gstime::gstime;
+use crate::propagation::sgp4::{sgp4, Sgp4Error, Sgp4Result};
+use crate::transforms::{eci_to_ecf, state_to_teme, Frame};
+use crate::vector::{add, cross, dot, norm, scale, Vec3};
+use crate::{EcfVec3, EciVec3, SatRec};
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Threshold on the universal variable products below which the Stumpff
+/// functions use their series limits and the orbit counts as parabolic.
+const SMALL: f64 = 1e-6;
+
+/// Convergence tolerance on the universal anomaly, √km.
+const CHI_TOLERANCE: f64 = 1e-9;
+
+const MAX_ITERATIONS: usize = 50;
+
+/// Position (km) and velocity (km/s) of an object; for the states returned
+/// by a [`Propagator`], in its [`frame`](Propagator::frame).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct StateVector {
+    position: EciVec3,
+    velocity: EciVec3,
+}
+
+impl StateVector {
+    pub fn new(position: EciVec3, velocity: EciVec3) -> StateVector {
+        StateVector { position, velocity }
+    }
+
+    pub fn position(&self) -> &EciVec3 {
+        &self.position
+    }
+
+    pub fn velocity(&self) -> &EciVec3 {
+        &self.velocity
+    }
+}
+
+impl From<Sgp4Result> for StateVector {
+    fn from(result: Sgp4Result) -> StateVector {
+        StateVector::new(*result.position(), *result.velocity())
+    }
+}
+
+/// Why a [`Propagator`] could not produce a state.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum PropagatorError {
+    /// SGP4 failed on the element set.
+    Sgp4(Sgp4Error),
+    /// The date lies outside the [`valid_range`](Propagator::valid_range).
+    OutOfRange,
+    /// A sampling window ends before it starts or has a step that is not
+    /// positive.
+    InvalidWindow,
+    /// An ephemeris table has fewer than two samples, vectors of different
+    /// lengths or dates that do not increase.
+    InvalidTable,
+    /// A numerically propagated satellite fell below the Earth's surface.
+    Decayed,
+    /// The integration tolerance is not a finite number of at least
+    /// 1e-15.
+    InvalidTolerance,
+    /// The integrator had to shrink its step below a microsecond to meet
+    /// the tolerance.
+    StepSizeTooSmall,
+    /// Kepler's equation did not converge within the iteration limit, as
+    /// for a date or state that is not finite.
+    NotConverged,
+}
+
+impl From<Sgp4Error> for PropagatorError {
+    fn from(error: Sgp4Error) -> PropagatorError {
+        PropagatorError::Sgp4(error)
+    }
+}
+
+/// A source of position and velocity states over time.
+pub trait Propagator {
+    /// Position (km) and velocity (km/s) at Julian date `jd`, in
+    /// [`frame`](Propagator::frame).
+    fn state_at(&mut self, jd: f64) -> Result<StateVector, PropagatorError>;
+
+    /// First and last Julian dates at which the states can be trusted.
+    /// Functions sampling a propagator over a window return
+    /// [`PropagatorError::OutOfRange`] when the window leaves this range.
+    fn valid_range(&self) -> (f64, f64);
+
+    /// Frame of the states returned by [`state_at`](Propagator::state_at).
+    fn frame(&self) -> Frame;
+
+    /// Catalog number of the propagated object, when known. Conjunction
+    /// screening skips catalog entries with the same number.
+    fn satnum(&self) -> Option<String> {
+        None
+    }
+}
+
+impl Propagator for SatRec {
+    fn state_at(&mut self, jd: f64) -> Result<StateVector, PropagatorError> {
+        let result = sgp4(self, (jd - self.jdsatepoch) * MINUTES_PER_DAY)?;
+        Ok(result.into())
+    }
+
+    /// SGP4 runs at any date; its accuracy degrades by a few kilometres a
+    /// day away from the epoch.
+    fn valid_range(&self) -> (f64, f64) {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    fn frame(&self) -> Frame {
+        Frame::Teme
+    }
+
+    fn satnum(&self) -> Option<String> {
+        Some(SatRec::satnum(self))
+    }
+}
+
+/// Check that the window from `start` to `end` lies within the valid range
+/// of `propagator`.
+pub(crate) fn check_range<P: Propagator + ?Sized>(
+    propagator: &P,
+    start: f64,
+    end: f64,
+) -> Result<(), PropagatorError> {
+    let (first, last) = propagator.valid_range();
+    if start < first || end > last {
+        return Err(PropagatorError::OutOfRange);
+    }
+    Ok(())
+}
+
+/// State of `propagator` at `jd` in TEME.
+pub(crate) fn teme_state<P: Propagator + ?Sized>(
+    propagator: &mut P,
+    jd: f64,
+) -> Result<StateVector, PropagatorError> {
+    let state = propagator.state_at(jd)?;
+    if propagator.frame() == Frame::Teme {
+        return Ok(state);
+    }
+    let (position, velocity) = state_to_teme(
+        propagator.frame(),
+        jd,
+        &state.position().into(),
+        &state.velocity().into(),
+    );
+    Ok(StateVector::new(position.into(), velocity.into()))
+}
+
+/// Earth-fixed position of `propagator` at `jd`, km.
+pub(crate) fn ecf_position<P: Propagator + ?Sized>(
+    propagator: &mut P,
+    jd: f64,
+) -> Result<EcfVec3, PropagatorError> {
+    let state = propagator.state_at(jd)?;
+    Ok(match propagator.frame() {
+        Frame::Ecef => {
+            let position = state.position();
+            EcfVec3::new(position.x, position.y, position.z)
+        }
+        Frame::Teme => eci_to_ecf(state.position(), gstime(jd)),
+        Frame::J2000 => {
+            let velocity = [0.0; 3];
+            let (position, _) =
+                state_to_teme(Frame::J2000, jd, &state.position().into(), &velocity);
+            eci_to_ecf(&position.into(), gstime(jd))
+        }
+    })
+}
+
+// Stumpff functions c2(ψ) and c3(ψ).
+fn stumpff(psi: f64) -> (f64, f64) {
+    if psi > SMALL {
+        let s = psi.sqrt();
+        ((1.0 - s.cos()) / psi, (s - s.sin()) / (s * psi))
+    } else if psi < -SMALL {
+        let s = (-psi).sqrt();
+        ((1.0 - s.cosh()) / psi, (s.sinh() - s) / (s * -psi))
+    } else {
+        (0.5, 1.0 / 6.0)
+    }
+}
+
+/// Two-body propagator solving Kepler's problem with universal variables,
+/// valid for elliptic, parabolic and hyperbolic orbits alike.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct KeplerPropagator {
+    epoch: f64,
+    position: Vec3,
+    velocity: Vec3,
+    mu: f64,
+    frame: Frame,
+    satnum: Option<String>,
+}
+
+#[wasm_bindgen]
+impl KeplerPropagator {
+    /// Propagator through the state `position` (km), `velocity` (km/s) at
+    /// Julian date `epoch`, given in `frame`. Earth-fixed states are
+    /// converted to TEME, in which the propagator then works.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        epoch: f64,
+        position: &EciVec3,
+        velocity: &EciVec3,
+        frame: Frame,
+        gravity: GravityModel,
+    ) -> KeplerPropagator {
+        let (position, velocity, frame) = match frame {
+            Frame::Ecef => {
+                let (r, v) = state_to_teme(frame, epoch, &position.into(), &velocity.into());
+                (r, v, Frame::Teme)
+            }
+            _ => (position.into(), velocity.into(), frame),
+        };
+        KeplerPropagator {
+            epoch,
+            position,
+            velocity,
+            mu: gravity.mu(),
+            frame,
+            satnum: None,
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> f64 {
+        self.epoch
+    }
+}
+
+impl KeplerPropagator {
+    /// Propagator through the TEME state of `satrec` at Julian date `jd`,
+    /// carrying its catalog number.
+    pub fn from_satrec(satrec: &mut SatRec, jd: f64) -> Result<KeplerPropagator, Sgp4Error> {
+        let state = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+        Ok(KeplerPropagator {
+            satnum: Some(satrec.satnum()),
+            ..KeplerPropagator::new(
+                jd,
+                state.position(),
+                state.velocity(),
+                Frame::Teme,
+                GravityModel::Wgs72,
+            )
+        })
+    }
+
+    /// Position and velocity `dt` seconds after the epoch, after Vallado's
+    /// `kepler` (algorithm 8).
+    fn propagate(&self, dt: f64) -> Result<(Vec3, Vec3), PropagatorError> {
+        if dt == 0.0 {
+            return Ok((self.position, self.velocity));
+        }
+        let mu = self.mu;
+        let sqrt_mu = mu.sqrt();
+        let r0 = norm(&self.position);
+        let v0 = norm(&self.velocity);
+        let rdotv = dot(&self.position, &self.velocity);
+        let alpha = -v0 * v0 / mu + 2.0 / r0;
+
+        // starting guess for the universal anomaly
+        let mut chi = if alpha > SMALL {
+            sqrt_mu * dt * alpha
+        } else if alpha < -SMALL {
+            let a = 1.0 / alpha;
+            let sign = dt.signum();
+            sign * (-a).sqrt()
+                * ((-2.0 * mu * alpha * dt)
+                    / (rdotv + sign * (-mu * a).sqrt() * (1.0 - r0 * alpha)))
+                    .ln()
+        } else {
+            let h = cross(&self.position, &self.velocity);
+            let p = dot(&h, &h) / mu;
+            let s = 0.5 * (1.0 / (3.0 * (mu / p.powi(3)).sqrt() * dt)).atan();
+            let w = s.tan().cbrt().atan();
+            p.sqrt() * 2.0 / (2.0 * w).tan()
+        };
+
+        let mut r = r0;
+        let (mut c2, mut c3) = (0.5, 1.0 / 6.0);
+        let mut psi = 0.0;
+        let mut converged = false;
+        for _ in 0..MAX_ITERATIONS {
+            psi = chi * chi * alpha;
+            (c2, c3) = stumpff(psi);
+            r = chi * chi * c2 + rdotv / sqrt_mu * chi * (1.0 - psi * c3) + r0 * (1.0 - psi * c2);
+            let step = (sqrt_mu * dt
+                - chi * chi * chi * c3
+                - rdotv / sqrt_mu * chi * chi * c2
+                - r0 * chi * (1.0 - psi * c3))
+                / r;
+            chi += step;
+            if step.abs() < CHI_TOLERANCE {
+                converged = true;
+                break;
+            }
+        }
+        if !converged {
+            return Err(PropagatorError::NotConverged);
+        }
+
+        let chi2 = chi * chi;
+        let f = 1.0 - chi2 * c2 / r0;
+        let g = dt - chi2 * chi * c3 / sqrt_mu;
+        let gdot = 1.0 - chi2 * c2 / r;
+        let fdot = sqrt_mu / (r * r0) * chi * (psi * c3 - 1.0);
+        Ok((
+            add(&scale(&self.position, f), &scale(&self.velocity, g)),
+            add(&scale(&self.position, fdot), &scale(&self.velocity, gdot)),
+        ))
+    }
+}
+
+impl Propagator for KeplerPropagator {
+    fn state_at(&mut self, jd: f64) -> Result<StateVector, PropagatorError> {
+        let (position, velocity) = self.propagate((jd - self.epoch) * 86400.0)?;
+        Ok(StateVector::new(position.into(), velocity.into()))
+    }
+
+    fn valid_range(&self) -> (f64, f64) {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    fn frame(&self) -> Frame {
+        self.frame
+    }
+
+    fn satnum(&self) -> Option<String> {
+        self.satnum.clone()
+    }
+}
+
+/// Propagator through the TEME state of `satrec` at Julian date `jd`.
+#[wasm_bindgen(js_name = "keplerFromSatRec")]
+pub fn js_kepler_from_satrec(satrec: &mut SatRec, jd: f64) -> Result<KeplerPropagator, JsValue> {
+    KeplerPropagator::from_satrec(satrec, jd).map_err(|sgp4_error| to_value(&sgp4_error).unwrap())
+}
+
+#[wasm_bindgen(js_name = "keplerStateAt")]
+pub fn js_kepler_state_at(propagator: &mut KeplerPropagator, jd: f64) -> Result<JsValue, JsValue> {
+    match propagator.state_at(jd) {
+        Ok(state) => Ok(to_value(&state).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::TWO_PI;
+    use crate::elements::{coe2rv, rv2coe, ClassicalElements};
+    use crate::vector::sub;
+    use std::f64::consts::PI;
+
+    fn propagator(p: f64, ecc: f64, nu: f64) -> KeplerPropagator {
+        let state = coe2rv(
+            &ClassicalElements {
+                p,
+                a: f64::NAN,
+                ecc,
+                incl: 0.7,
+                raan: 1.2,
+                argp: 0.3,
+                nu,
+                m: f64::NAN,
+                arglat: f64::NAN,
+                truelon: f64::NAN,
+                lonper: f64::NAN,
+            },
+            GravityModel::Wgs84,
+        );
+        KeplerPropagator::new(
+            2460000.5,
+            state.position(),
+            state.velocity(),
+            Frame::J2000,
+            GravityModel::Wgs84,
+        )
+    }
+
+    // Energy and angular momentum are conserved along any conic; the energy
+    // is compared to the potential, as it vanishes on a parabola.
+    fn assert_integrals(propagator: &mut KeplerPropagator, days: f64) {
+        let mu = propagator.mu;
+        let r0 = propagator.position;
+        let v0 = propagator.velocity;
+        let state = propagator.state_at(propagator.epoch + days).unwrap();
+        let r: Vec3 = state.position().into();
+        let v: Vec3 = state.velocity().into();
+        let energy = |r: &Vec3, v: &Vec3| 0.5 * dot(v, v) - mu / norm(r);
+        assert!((energy(&r, &v) - energy(&r0, &v0)).abs() < 1e-9 * mu / norm(&r0));
+        let h0 = cross(&r0, &v0);
+        assert!(norm(&sub(&cross(&r, &v), &h0)) / norm(&h0) < 1e-9);
+    }
+
+    #[test]
+    fn test_elliptic_period() {
+        let mut propagator = propagator(7000.0, 0.1, 0.5);
+        let elements = rv2coe(
+            &propagator.position.into(),
+            &propagator.velocity.into(),
+            GravityModel::Wgs84,
+        );
+        let period = TWO_PI * (elements.a.powi(3) / propagator.mu).sqrt() / 86400.0;
+
+        let state = propagator.state_at(propagator.epoch + period).unwrap();
+        let r: Vec3 = state.position().into();
+        assert!(norm(&sub(&r, &propagator.position)) < 1e-4);
+
+        let elements = rv2coe(state.position(), state.velocity(), GravityModel::Wgs84);
+        assert!((elements.nu - 0.5).abs() < 1e-8);
+        assert_integrals(&mut propagator, 0.37 * period);
+        assert_integrals(&mut propagator, -3.2 * period);
+    }
+
+    #[test]
+    fn test_parabolic_and_hyperbolic() {
+        for ecc in [1.0, 1.8] {
+            let mut propagator = propagator(9000.0, ecc, -0.4);
+            for days in [-0.01, 0.02, 0.3] {
+                assert_integrals(&mut propagator, days);
+            }
+            let state = propagator.state_at(propagator.epoch + 0.1).unwrap();
+            let elements = rv2coe(state.position(), state.velocity(), GravityModel::Wgs84);
+            assert!((elements.ecc - ecc).abs() < 1e-6);
+            assert!(elements.nu > 0.0 && elements.nu < PI);
+        }
+    }
+
+    #[test]
+    fn test_not_converged() {
+        let mut propagator = propagator(7000.0, 0.1, 0.5);
+        for jd in [f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                propagator.state_at(jd).unwrap_err(),
+                PropagatorError::NotConverged
+            );
+        }
+    }
+
+    #[test]
+    fn test_satrec_propagator() {
+        let mut satrec = crate::io::twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let jd = satrec.jdsatepoch + 0.1;
+        let state = satrec.state_at(jd).unwrap();
+        let mut kepler = KeplerPropagator::from_satrec(&mut satrec, jd).unwrap();
+
+        // two-body motion drifts from SGP4 by kilometres over an orbit
+        let later = satrec.state_at(jd + 0.06).unwrap();
+        let two_body = kepler.state_at(jd + 0.06).unwrap();
+        let r: Vec3 = later.position().into();
+        let drift = norm(&sub(&r, &two_body.position().into()));
+        assert!(drift > 0.1 && drift < 50.0);
+
+        let position = ecf_position(&mut kepler, jd).unwrap();
+        let expected = eci_to_ecf(state.position(), gstime(jd));
+        assert!((position.x - expected.x).abs() < 1e-9);
+        assert_eq!(kepler.frame(), Frame::Teme);
+    }
+}
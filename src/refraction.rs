@@ -0,0 +1,171 @@
+use crate::constants::{DEG2RAD, RAD2DEG};
+use crate::transforms::ecf_to_look_angles;
+use crate::{EcfVec3, GeodeticLocation, LookAngles};
+use wasm_bindgen::prelude::*;
+
+/// Lowest elevation, in degrees, at which the refraction formulas are evaluated.
+/// Below it the correction is held constant instead of diverging.
+const MIN_ELEVATION_DEG: f64 = -1.0;
+
+/// Surface refractivity (N-units) of the dry 10 °C / 1010 hPa atmosphere the
+/// Saemundsson formula is calibrated for.
+const STANDARD_REFRACTIVITY: f64 = 77.6 * 1010.0 / 283.15;
+
+/// Refraction model applied to geometric elevations.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefractionModel {
+    /// Bennett (1982) optical refraction, inverted to act on geometric elevations.
+    Bennett,
+    /// Saemundsson (1986) optical refraction.
+    Saemundsson,
+    /// Radio refraction scaled by the surface refractivity (ITU-R P.453),
+    /// which depends on temperature, pressure and humidity.
+    Radio,
+}
+
+/// Surface weather at the observer.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct Atmosphere {
+    /// Air temperature in °C.
+    pub temperature: f64,
+    /// Air pressure in hPa (mbar).
+    pub pressure: f64,
+    /// Relative humidity in percent, 0 .. 100.
+    pub humidity: f64,
+}
+
+#[wasm_bindgen]
+impl Atmosphere {
+    #[wasm_bindgen(constructor)]
+    pub fn new(temperature: f64, pressure: f64, humidity: f64) -> Atmosphere {
+        Atmosphere {
+            temperature,
+            pressure,
+            humidity,
+        }
+    }
+
+    /// 10 °C, 1010 hPa and dry air, the conditions the optical formulas assume.
+    pub fn standard() -> Atmosphere {
+        Atmosphere {
+            temperature: 10.0,
+            pressure: 1010.0,
+            humidity: 0.0,
+        }
+    }
+
+    /// Surface refractivity in N-units, `(n - 1) * 1e6`.
+    pub fn refractivity(&self) -> f64 {
+        let t = self.temperature + 273.15;
+        // Buck (1981) saturation vapour pressure over water, hPa.
+        let es = 6.1121 * ((17.502 * self.temperature) / (self.temperature + 240.97)).exp();
+        let e = (self.humidity / 100.0) * es;
+        (77.6 / t) * (self.pressure + 4810.0 * e / t)
+    }
+
+    fn optical_scale(&self) -> f64 {
+        (self.pressure / 1010.0) * (283.0 / (273.0 + self.temperature))
+    }
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Atmosphere::standard()
+    }
+}
+
+// Saemundsson: true elevation in degrees to refraction in arcminutes.
+fn saemundsson_arcmin(elevation_deg: f64) -> f64 {
+    let h = elevation_deg.max(MIN_ELEVATION_DEG);
+    1.02 / ((h + 10.3 / (h + 5.11)) * DEG2RAD).tan()
+}
+
+// Bennett: apparent elevation in degrees to refraction in arcminutes.
+fn bennett_arcmin(apparent_deg: f64) -> f64 {
+    let h = apparent_deg.max(MIN_ELEVATION_DEG);
+    1.0 / ((h + 7.31 / (h + 4.4)) * DEG2RAD).tan()
+}
+
+/// Refraction correction in radians to add to a geometric elevation (radians)
+/// to obtain the observed elevation.
+///
+/// The Bennett formula maps apparent to true elevation, so it is inverted by
+/// fixed-point iteration. The radio model scales Saemundsson by the ratio of
+/// the surface refractivity to that of the standard optical atmosphere, which
+/// adds the wet term that dominates at radio frequencies. Accuracy is about
+/// 0.1 arcminute above 15° and degrades to a few arcminutes at the horizon.
+#[wasm_bindgen]
+pub fn refraction(elevation: f64, model: RefractionModel, atmosphere: &Atmosphere) -> f64 {
+    let elevation_deg = elevation * RAD2DEG;
+    let arcmin = match model {
+        RefractionModel::Saemundsson => {
+            saemundsson_arcmin(elevation_deg) * atmosphere.optical_scale()
+        }
+        RefractionModel::Bennett => {
+            let scale = atmosphere.optical_scale();
+            let mut r = saemundsson_arcmin(elevation_deg) * scale;
+            for _ in 0..4 {
+                r = bennett_arcmin(elevation_deg + r / 60.0) * scale;
+            }
+            r
+        }
+        RefractionModel::Radio => {
+            saemundsson_arcmin(elevation_deg) * atmosphere.refractivity() / STANDARD_REFRACTIVITY
+        }
+    };
+    // the formulas dip slightly below zero close to the zenith
+    (arcmin.max(0.0) / 60.0) * DEG2RAD
+}
+
+/// Look angles as in `ecf_to_look_angles`, with the elevation corrected for
+/// atmospheric refraction. Azimuth and range are left geometric.
+#[wasm_bindgen(js_name = "ecfToLookAnglesRefracted")]
+pub fn ecf_to_look_angles_refracted(
+    observer_geodetic: &GeodeticLocation,
+    satellite_ecf: &EcfVec3,
+    model: RefractionModel,
+    atmosphere: &Atmosphere,
+) -> LookAngles {
+    let look_angles = ecf_to_look_angles(observer_geodetic, satellite_ecf);
+    let elevation = look_angles.elevation();
+    LookAngles::new(
+        look_angles.azimuth(),
+        elevation + refraction(elevation, model, atmosphere),
+        look_angles.range_sat(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arcmin(elevation_deg: f64, model: RefractionModel, atmosphere: &Atmosphere) -> f64 {
+        refraction(elevation_deg * DEG2RAD, model, atmosphere) * RAD2DEG * 60.0
+    }
+
+    #[test]
+    fn test_optical_refraction() {
+        let atmosphere = Atmosphere::standard();
+        for model in [RefractionModel::Bennett, RefractionModel::Saemundsson] {
+            // about 29' at a geometric 0° (34' at an apparent 0°), 5.35' at 10°, 1' at 45°
+            assert!((arcmin(0.0, model, &atmosphere) - 29.0).abs() < 0.5);
+            assert!((arcmin(10.0, model, &atmosphere) - 5.35).abs() < 0.1);
+            assert!((arcmin(45.0, model, &atmosphere) - 1.0).abs() < 0.05);
+            assert!(arcmin(90.0, model, &atmosphere).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_radio_refraction_humidity() {
+        let dry = Atmosphere::standard();
+        let humid = Atmosphere::new(25.0, 1013.25, 80.0);
+
+        assert!((dry.refractivity() - STANDARD_REFRACTIVITY).abs() < 1e-9);
+        assert!(humid.refractivity() > 340.0 && humid.refractivity() < 380.0);
+        assert!(
+            arcmin(5.0, RefractionModel::Radio, &humid) > arcmin(5.0, RefractionModel::Radio, &dry)
+        );
+    }
+}
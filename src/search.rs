@@ -0,0 +1,61 @@
+//! Scalar root and extremum searches used by the event finders.
+//!
+//! The functions evaluated here are usually backed by `sgp4`, which can fail,
+//! so every callback returns a `Result` and errors are passed straight through.
+
+/// Convergence tolerance on time, in days (about 10 ms).
+pub const TIME_TOLERANCE: f64 = 1e-7;
+
+const GOLDEN: f64 = 0.618_033_988_749_894_8;
+
+/// Find a zero of `f` in `[a, b]` by bisection. `f(a)` and `f(b)` must have
+/// opposite signs; the root is located to within `tol`.
+pub fn find_root<E, F>(mut f: F, mut a: f64, mut b: f64, tol: f64) -> Result<f64, E>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    let mut fa = f(a)?;
+    while (b - a).abs() > tol {
+        let m = 0.5 * (a + b);
+        let fm = f(m)?;
+        if fm == 0.0 {
+            return Ok(m);
+        }
+        if (fa < 0.0) == (fm < 0.0) {
+            a = m;
+            fa = fm;
+        } else {
+            b = m;
+        }
+    }
+    Ok(0.5 * (a + b))
+}
+
+/// Find the maximum of a unimodal `f` in `[a, b]` by golden-section search.
+/// Returns the abscissa and the value of the maximum.
+pub fn find_maximum<E, F>(mut f: F, mut a: f64, mut b: f64, tol: f64) -> Result<(f64, f64), E>
+where
+    F: FnMut(f64) -> Result<f64, E>,
+{
+    let mut c = b - GOLDEN * (b - a);
+    let mut d = a + GOLDEN * (b - a);
+    let mut fc = f(c)?;
+    let mut fd = f(d)?;
+    while (b - a).abs() > tol {
+        if fc > fd {
+            b = d;
+            d = c;
+            fd = fc;
+            c = b - GOLDEN * (b - a);
+            fc = f(c)?;
+        } else {
+            a = c;
+            c = d;
+            fc = fd;
+            d = a + GOLDEN * (b - a);
+            fd = f(d)?;
+        }
+    }
+    let x = 0.5 * (a + b);
+    Ok((x, f(x)?))
+}
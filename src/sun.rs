@@ -0,0 +1,108 @@
+use crate::constants::{AU, DEG2RAD};
+use crate::propagation::gstime::gstime;
+use crate::transforms::{eci_to_ecf, mod_to_teme_matrix, teme_to_j2000};
+use crate::vector::{mat_vec, Vec3};
+use crate::{EcfVec3, EciVec3};
+use wasm_bindgen::prelude::*;
+
+/* -----------------------------------------------------------------------------
+ *
+ *                           function sun
+ *
+ *  this function calculates the geocentric equatorial position vector
+ *    the sun given the julian date. this is the low precision formula and
+ *    is valid for years from 1950 to 2050. accuaracy of apparent coordinates
+ *    is 0.01 degrees. notice many of the calculations are performed in
+ *    degrees, and are not changed until later. this is due to the fact that
+ *    the almanac uses degrees exclusively in their formulations.
+ *
+ *  author        : david vallado                  719-573-2600   27 may 2002
+ *
+ *  inputs          description                    range / units
+ *    jd          - julian date                    days from 4713 bc
+ *
+ *  outputs       :
+ *    rsun        - mod position vector              km
+ *
+ *  references    :
+ *    vallado       2013, 279, alg 29, ex 5-1
+ * --------------------------------------------------------------------------- */
+fn sun_position_mod(jd: f64) -> Vec3 {
+    let tut1 = (jd - 2451545.0) / 36525.0;
+
+    let meanlong = (280.460 + 36000.771 * tut1) % 360.0;
+    // ttdb is approximated by tut1, well below the accuracy of the model
+    let meananomaly = ((357.5291092 + 35999.05034 * tut1) % 360.0) * DEG2RAD;
+
+    let eclplong =
+        (meanlong + 1.914666471 * meananomaly.sin() + 0.019994643 * (2.0 * meananomaly).sin())
+            * DEG2RAD;
+    let obliquity = (23.439291 - 0.0130042 * tut1) * DEG2RAD;
+
+    let magr =
+        1.000140612 - 0.016708617 * meananomaly.cos() - 0.000139589 * (2.0 * meananomaly).cos();
+
+    [
+        magr * AU * eclplong.cos(),
+        magr * AU * obliquity.cos() * eclplong.sin(),
+        magr * AU * obliquity.sin() * eclplong.sin(),
+    ]
+}
+
+/// Geocentric position of the Sun in the TEME frame, km, at Julian date `jd`.
+///
+/// Uses the Astronomical Almanac low-precision solar coordinates (Vallado
+/// alg. 29): about 0.01° in direction and 1e-4 AU in distance between 1950
+/// and 2050.
+#[wasm_bindgen(js_name = "sunPositionTeme")]
+pub fn sun_position_teme(jd: f64) -> EciVec3 {
+    mat_vec(&mod_to_teme_matrix(jd), &sun_position_mod(jd)).into()
+}
+
+/// Geocentric position of the Sun in the J2000 frame, km.
+#[wasm_bindgen(js_name = "sunPositionJ2000")]
+pub fn sun_position_j2000(jd: f64) -> EciVec3 {
+    teme_to_j2000(&sun_position_teme(jd), jd)
+}
+
+/// Position of the Sun in the Earth-fixed frame, km.
+#[wasm_bindgen(js_name = "sunPositionEcf")]
+pub fn sun_position_ecf(jd: f64) -> EcfVec3 {
+    eci_to_ecf(&sun_position_teme(jd), gstime(jd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::RAD2DEG;
+    use crate::ext::jday;
+
+    fn declination(r: &EciVec3) -> f64 {
+        (r.z / (r.x * r.x + r.y * r.y + r.z * r.z).sqrt()).asin() * RAD2DEG
+    }
+
+    #[test]
+    fn test_sun_perihelion_distance() {
+        // perihelion 2024-01-03 00:39 UTC at 0.9833070 AU
+        let r = sun_position_teme(jday(2024.0, 1.0, 3.0, 0.0, 39.0, 0.0, 0.0));
+        let distance = (r.x * r.x + r.y * r.y + r.z * r.z).sqrt() / AU;
+        assert!((distance - 0.983307).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_sun_equinox_and_solstice() {
+        // March equinox 2024-03-20 03:06 UTC and June solstice 2024-06-20 20:51 UTC
+        let equinox = sun_position_teme(jday(2024.0, 3.0, 20.0, 3.0, 6.0, 0.0, 0.0));
+        let solstice = sun_position_teme(jday(2024.0, 6.0, 20.0, 20.0, 51.0, 0.0, 0.0));
+        assert!(declination(&equinox).abs() < 0.01);
+        assert!(equinox.x > 0.0 && (equinox.y / equinox.x).abs() < 2e-4);
+        assert!((declination(&solstice) - 23.4387).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_sun_ecf_at_noon() {
+        // apparent solar noon at Greenwich on 2024-06-20 is about 12:01:35 UTC
+        let sun = sun_position_ecf(jday(2024.0, 6.0, 20.0, 12.0, 1.0, 35.0, 0.0));
+        assert!((sun.y.atan2(sun.x) * RAD2DEG).abs() < 0.05);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::constants::{DEG2RAD, PI, RAD2DEG, TWO_PI};
+use crate::constants::{DEG2RAD, OMEGA_EARTH, PI, RAD2DEG, TWO_PI};
+use crate::propagation::gstime::gstime;
+use crate::vector::{add, cross, mat_mul, mat_vec, rot1, rot2, rot3, sub, transpose, Mat3, Vec3};
 use crate::LookAngles;
 use crate::GeodeticLocation;
 use crate::Topocentric;
@@ -6,11 +8,13 @@ use crate::EciVec3;
 use crate::EcfVec3;
 use crate::RangeErr;
 extern crate wasm_bindgen;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 
-const A: f64 = 6378.137;
-const B: f64 = 6356.7523142;
+/// WGS84 semi-major and semi-minor axes, km.
+pub(crate) const A: f64 = 6378.137;
+pub(crate) const B: f64 = 6356.7523142;
 const F: f64 = (A - B) / A;
 const E2: f64 = (2.0 * F) - (F * F);
 
@@ -117,6 +121,13 @@ pub fn eci_to_geodetic(eci: &EciVec3, gmst: f64) -> GeodeticLocation {
     }
 }
 
+/// Geodetic coordinates of the Earth-fixed position `ecf` (km).
+#[wasm_bindgen(js_name = "ecfToGeodetic")]
+pub fn ecf_to_geodetic(ecf: &EcfVec3) -> GeodeticLocation {
+    // with a zero sidereal angle the inertial and Earth-fixed frames coincide
+    eci_to_geodetic(&EciVec3::new(ecf.x, ecf.y, ecf.z), 0.0)
+}
+
 #[wasm_bindgen(js_name="eciToEcf")]
 pub fn ecf_to_eci(ecf: &EcfVec3, gmst: f64) -> EciVec3 {
     //
@@ -152,7 +163,8 @@ pub fn eci_to_ecf(eci: &EciVec3, gmst: f64) -> EcfVec3 {
     EcfVec3 { x, y, z }
 }
 
- fn topocentric(observer_geodetic: &GeodeticLocation, satellite_ecf: &EcfVec3) -> Topocentric {
+#[wasm_bindgen]
+pub fn topocentric(observer_geodetic: &GeodeticLocation, satellite_ecf: &EcfVec3) -> Topocentric {
     let latitude = observer_geodetic.latitude;
     let longitude = observer_geodetic.longitude;
     let observer_ecf = geodetic_to_ecf(observer_geodetic);
@@ -164,11 +176,11 @@ pub fn eci_to_ecf(eci: &EciVec3, gmst: f64) -> EcfVec3 {
     let top_s = (latitude.sin() * longitude.cos() * rx) + (latitude.sin() * longitude.sin() * ry)
         - (latitude.cos() * rz);
 
-    let top_e = (longitude.sin() * rx) + (longitude.cos() * ry);
+    let top_e = (-longitude.sin() * rx) + (longitude.cos() * ry);
 
     let top_z = (latitude.cos() * longitude.cos() * rx)
         + (latitude.cos() * longitude.sin() * ry)
-        + latitude * rz;
+        + (latitude.sin() * rz);
 
     Topocentric {
         top_s,
@@ -177,7 +189,8 @@ pub fn eci_to_ecf(eci: &EciVec3, gmst: f64) -> EcfVec3 {
     }
 }
 
- fn topocentric_to_look_angles(tc: &Topocentric) -> LookAngles {
+#[wasm_bindgen(js_name="topocentricToLookAngles")]
+pub fn topocentric_to_look_angles(tc: &Topocentric) -> LookAngles {
     let top_s = tc.top_s;
     let top_e = tc.top_e;
     let top_z = tc.top_z;
@@ -198,3 +211,196 @@ pub fn ecf_to_look_angles(observer_geodetic: &GeodeticLocation, satellite_ecf: &
     let topocentric_coords = topocentric(observer_geodetic, satellite_ecf);
     topocentric_to_look_angles(&topocentric_coords)
 }
+
+/// Seconds of arc to radians.
+const ARCSEC2RAD: f64 = DEG2RAD / 3600.0;
+
+// IAU-76 precession, J2000 to mean-of-date. Vallado 2013, eq 3-88 to 3-90.
+fn precession(jd: f64) -> Mat3 {
+    let t = (jd - 2451545.0) / 36525.0;
+    let zeta = (2306.2181 * t + 0.30188 * t * t + 0.017998 * t * t * t) * ARCSEC2RAD;
+    let theta = (2004.3109 * t - 0.42665 * t * t - 0.041833 * t * t * t) * ARCSEC2RAD;
+    let z = (2306.2181 * t + 1.09468 * t * t + 0.018203 * t * t * t) * ARCSEC2RAD;
+    mat_mul(&mat_mul(&rot3(-z), &rot2(theta)), &rot3(-zeta))
+}
+
+// Nutation in longitude and obliquity plus the mean obliquity, radians, from
+// the four largest terms of the IAU-80 series (Meeus, ch. 22; about 0.5").
+fn nutation_angles(jd: f64) -> (f64, f64, f64) {
+    let t = (jd - 2451545.0) / 36525.0;
+    let omega = (125.04452 - 1934.136261 * t) * DEG2RAD;
+    let l_sun = (280.4665 + 36000.7698 * t) * DEG2RAD;
+    let l_moon = (218.3165 + 481267.8813 * t) * DEG2RAD;
+
+    let dpsi = (-17.20 * omega.sin() - 1.32 * (2.0 * l_sun).sin() - 0.23 * (2.0 * l_moon).sin()
+        + 0.21 * (2.0 * omega).sin())
+        * ARCSEC2RAD;
+    let deps = (9.20 * omega.cos() + 0.57 * (2.0 * l_sun).cos() + 0.10 * (2.0 * l_moon).cos()
+        - 0.09 * (2.0 * omega).cos())
+        * ARCSEC2RAD;
+    let eps_mean = (84381.448 - 46.8150 * t - 0.00059 * t * t + 0.001813 * t * t * t) * ARCSEC2RAD;
+    (dpsi, deps, eps_mean)
+}
+
+/// Rotation from the mean equator and equinox of date to TEME.
+pub(crate) fn mod_to_teme_matrix(jd: f64) -> Mat3 {
+    let (dpsi, deps, eps_mean) = nutation_angles(jd);
+    let eps = eps_mean + deps;
+    let nutation = mat_mul(&mat_mul(&rot1(-eps), &rot3(-dpsi)), &rot1(eps_mean));
+    // TEME differs from true-of-date by the equation of the equinoxes.
+    mat_mul(&rot3(dpsi * eps.cos()), &nutation)
+}
+
+/// Rotation from J2000 (EME2000) to TEME.
+pub(crate) fn j2000_to_teme_matrix(jd: f64) -> Mat3 {
+    mat_mul(&mod_to_teme_matrix(jd), &precession(jd))
+}
+
+/// Rotate a TEME vector at Julian date `jd` into the J2000 frame, using
+/// IAU-76 precession and a truncated IAU-80 nutation (about 1" accuracy).
+#[wasm_bindgen(js_name="temeToJ2000")]
+pub fn teme_to_j2000(teme: &EciVec3, jd: f64) -> EciVec3 {
+    mat_vec(&transpose(&j2000_to_teme_matrix(jd)), &teme.into()).into()
+}
+
+/// Rotate a J2000 vector into the TEME frame of Julian date `jd`.
+#[wasm_bindgen(js_name="j2000ToTeme")]
+pub fn j2000_to_teme(j2000: &EciVec3, jd: f64) -> EciVec3 {
+    mat_vec(&j2000_to_teme_matrix(jd), &j2000.into()).into()
+}
+
+/// Reference frame of a position and velocity state.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Frame {
+    /// True equator, mean equinox: the frame of `sgp4` output.
+    Teme,
+    /// Mean equator and equinox of J2000 (EME2000).
+    J2000,
+    /// Earth-fixed, rotating with the Earth about the TEME pole.
+    Ecef,
+}
+
+/// Position (km) and velocity (km/s) at Julian date `jd` converted from
+/// `frame` to TEME. The slow rotation of J2000 with respect to TEME is
+/// neglected for the velocity.
+pub(crate) fn state_to_teme(
+    frame: Frame,
+    jd: f64,
+    position: &Vec3,
+    velocity: &Vec3,
+) -> (Vec3, Vec3) {
+    match frame {
+        Frame::Teme => (*position, *velocity),
+        Frame::J2000 => {
+            let m = j2000_to_teme_matrix(jd);
+            (mat_vec(&m, position), mat_vec(&m, velocity))
+        }
+        Frame::Ecef => {
+            let m = rot3(-gstime(jd));
+            let omega = [0.0, 0.0, OMEGA_EARTH];
+            (
+                mat_vec(&m, position),
+                mat_vec(&m, &add(velocity, &cross(&omega, position))),
+            )
+        }
+    }
+}
+
+/// Position (km) and velocity (km/s) at Julian date `jd` converted from TEME
+/// to `frame`; the inverse of [`state_to_teme`].
+pub(crate) fn state_from_teme(
+    frame: Frame,
+    jd: f64,
+    position: &Vec3,
+    velocity: &Vec3,
+) -> (Vec3, Vec3) {
+    match frame {
+        Frame::Teme => (*position, *velocity),
+        Frame::J2000 => {
+            let m = transpose(&j2000_to_teme_matrix(jd));
+            (mat_vec(&m, position), mat_vec(&m, velocity))
+        }
+        Frame::Ecef => {
+            let m = rot3(gstime(jd));
+            let r = mat_vec(&m, position);
+            let omega = [0.0, 0.0, OMEGA_EARTH];
+            (r, sub(&mat_vec(&m, velocity), &cross(&omega, &r)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_look_angles_overhead() {
+        let observer = GeodeticLocation::from_degrees(30.0, 45.0, 0.0);
+        let satellite = geodetic_to_ecf(&GeodeticLocation::from_degrees(30.0, 45.0, 500.0));
+
+        let look_angles = ecf_to_look_angles(&observer, &satellite);
+
+        assert!((look_angles.elevation_deg() - 90.0).abs() < 1e-3);
+        assert!((look_angles.range_sat() - 500.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_look_angles_azimuth() {
+        let observer = GeodeticLocation::from_degrees(0.0, 0.0, 0.0);
+        let east = EcfVec3::new(A, 1000.0, 0.0);
+        let north = EcfVec3::new(A, 0.0, 1000.0);
+
+        let look_east = ecf_to_look_angles(&observer, &east);
+        let look_north = ecf_to_look_angles(&observer, &north);
+
+        assert!((look_east.azimuth_deg() - 90.0).abs() < 1e-9);
+        assert!(look_east.elevation().abs() < 1e-9);
+        assert!((look_north.azimuth_deg() % 360.0).abs() < 1e-9);
+        assert!((look_north.range_sat() - 1000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ecf_to_geodetic() {
+        let location = GeodeticLocation::from_degrees(-120.0, -35.0, 420.0);
+        let back = ecf_to_geodetic(&geodetic_to_ecf(&location));
+        assert!((back.longitude - location.longitude).abs() < 1e-12);
+        assert!((back.latitude - location.latitude).abs() < 1e-12);
+        assert!((back.height - location.height).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_state_to_teme() {
+        let jd = 2460390.5;
+        let position = [7000.0, -1200.0, 300.0];
+        let velocity = [1.0, 7.0, 0.5];
+        let (r, _) = state_to_teme(Frame::J2000, jd, &position, &velocity);
+        let expected = j2000_to_teme(&EciVec3::new(7000.0, -1200.0, 300.0), jd);
+        assert!((r[0] - expected.x).abs() < 1e-9 && (r[2] - expected.z).abs() < 1e-9);
+
+        // a point fixed on the equator moves eastwards at 465 m/s
+        let (_, v) = state_to_teme(Frame::Ecef, jd, &[A, 0.0, 0.0], &[0.0, 0.0, 0.0]);
+        assert!((crate::vector::norm(&v) - 0.4651).abs() < 1e-4);
+
+        for frame in [Frame::J2000, Frame::Ecef] {
+            let (r, v) = state_to_teme(frame, jd, &position, &velocity);
+            let (r, v) = state_from_teme(frame, jd, &r, &v);
+            assert!(crate::vector::norm(&sub(&r, &position)) < 1e-9);
+            assert!(crate::vector::norm(&sub(&v, &velocity)) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_teme_j2000_round_trip() {
+        let jd = 2460390.5; // 2024-03-20
+        let teme = EciVec3::new(7000.0, -1200.0, 300.0);
+        let j2000 = teme_to_j2000(&teme, jd);
+        let back = j2000_to_teme(&j2000, jd);
+        assert!((back.x - teme.x).abs() < 1e-9 && (back.y - teme.y).abs() < 1e-9);
+
+        // 24 years of precession move the equinox by about 0.336°
+        let x = teme_to_j2000(&EciVec3::new(1.0, 0.0, 0.0), jd);
+        let angle = x.x.acos() * RAD2DEG;
+        assert!((angle - 0.336).abs() < 0.005);
+    }
+}
@@ -0,0 +1,110 @@
+//! Small fixed-size vector and matrix helpers shared by the analysis modules.
+
+use crate::{EcfVec3, EciVec3};
+
+pub type Vec3 = [f64; 3];
+pub type Mat3 = [[f64; 3]; 3];
+
+pub fn dot(a: &Vec3, b: &Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: &Vec3, b: &Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn norm(a: &Vec3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+pub fn add(a: &Vec3, b: &Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn sub(a: &Vec3, b: &Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale(a: &Vec3, k: f64) -> Vec3 {
+    [k * a[0], k * a[1], k * a[2]]
+}
+
+pub fn unit(a: &Vec3) -> Vec3 {
+    scale(a, 1.0 / norm(a))
+}
+
+pub fn mat_vec(m: &Mat3, v: &Vec3) -> Vec3 {
+    [dot(&m[0], v), dot(&m[1], v), dot(&m[2], v)]
+}
+
+pub fn mat_mul(a: &Mat3, b: &Mat3) -> Mat3 {
+    let mut m = [[0.0; 3]; 3];
+    for (i, row) in m.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = a[i][0] * b[0][j] + a[i][1] * b[1][j] + a[i][2] * b[2][j];
+        }
+    }
+    m
+}
+
+pub fn transpose(m: &Mat3) -> Mat3 {
+    [
+        [m[0][0], m[1][0], m[2][0]],
+        [m[0][1], m[1][1], m[2][1]],
+        [m[0][2], m[1][2], m[2][2]],
+    ]
+}
+
+/// Frame rotation about the x axis by `angle` radians.
+pub fn rot1(angle: f64) -> Mat3 {
+    let (s, c) = angle.sin_cos();
+    [[1.0, 0.0, 0.0], [0.0, c, s], [0.0, -s, c]]
+}
+
+/// Frame rotation about the y axis by `angle` radians.
+pub fn rot2(angle: f64) -> Mat3 {
+    let (s, c) = angle.sin_cos();
+    [[c, 0.0, -s], [0.0, 1.0, 0.0], [s, 0.0, c]]
+}
+
+/// Frame rotation about the z axis by `angle` radians.
+pub fn rot3(angle: f64) -> Mat3 {
+    let (s, c) = angle.sin_cos();
+    [[c, s, 0.0], [-s, c, 0.0], [0.0, 0.0, 1.0]]
+}
+
+impl From<&EciVec3> for Vec3 {
+    fn from(v: &EciVec3) -> Vec3 {
+        [v.x, v.y, v.z]
+    }
+}
+
+impl From<Vec3> for EciVec3 {
+    fn from(v: Vec3) -> EciVec3 {
+        EciVec3 {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+        }
+    }
+}
+
+impl From<&EcfVec3> for Vec3 {
+    fn from(v: &EcfVec3) -> Vec3 {
+        [v.x, v.y, v.z]
+    }
+}
+
+impl From<Vec3> for EcfVec3 {
+    fn from(v: Vec3) -> EcfVec3 {
+        EcfVec3 {
+            x: v[0],
+            y: v[1],
+            z: v[2],
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::constants::{DEG2RAD, MINUTES_PER_DAY};
+use crate::eclipse::shadow_margins;
+use crate::passes::{find_passes, look_angles_at, Pass};
+use crate::propagation::sgp4::{sgp4, Sgp4Error};
+use crate::propagator::PropagatorError;
+use crate::search::{find_root, TIME_TOLERANCE};
+use crate::sun::{sun_position_ecf, sun_position_teme};
+use crate::transforms::ecf_to_look_angles;
+use crate::{GeodeticLocation, SatRec};
+use serde::Serialize;
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+/// Sampling step, in seconds, used to bracket changes of visibility within a pass.
+const STEP_SECONDS: f64 = 10.0;
+
+/// How dark the sky must be at the observer for a pass to count as visible.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Twilight {
+    /// Sun at least 6° below the horizon.
+    Civil,
+    /// Sun at least 12° below the horizon.
+    Nautical,
+    /// Sun at least 18° below the horizon.
+    Astronomical,
+}
+
+impl Twilight {
+    /// Highest elevation of the Sun, in radians, for the sky to be dark enough.
+    pub fn sun_elevation(&self) -> f64 {
+        match self {
+            Twilight::Civil => -6.0 * DEG2RAD,
+            Twilight::Nautical => -12.0 * DEG2RAD,
+            Twilight::Astronomical => -18.0 * DEG2RAD,
+        }
+    }
+}
+
+/// Part of a pass during which the satellite is outside the umbra while the
+/// observer's sky is dark. Times are Julian dates, the elevation is in radians.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VisibleSegment {
+    pub start: f64,
+    pub end: f64,
+    pub max_elevation: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VisiblePass {
+    pub pass: Pass,
+    pub segments: Vec<VisibleSegment>,
+}
+
+// Both margins are positive while the satellite can be seen: the Sun is below
+// the twilight elevation at the observer and the satellite is not in umbra.
+fn visibility_margins(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    twilight: Twilight,
+    jd: f64,
+) -> Result<(f64, f64), Sgp4Error> {
+    let sun_elevation = ecf_to_look_angles(observer, &sun_position_ecf(jd)).elevation();
+    let result = sgp4(satrec, (jd - satrec.jdsatepoch) * MINUTES_PER_DAY)?;
+    let (_, umbra) = shadow_margins(&result.position().into(), &(&sun_position_teme(jd)).into());
+    Ok((twilight.sun_elevation() - sun_elevation, umbra))
+}
+
+fn visible_segments(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    twilight: Twilight,
+    pass: &Pass,
+) -> Result<Vec<VisibleSegment>, PropagatorError> {
+    let n = (pass.duration() / STEP_SECONDS).ceil().max(1.0) as usize;
+    let mut breaks = vec![pass.aos];
+    let mut t0 = pass.aos;
+    let (mut dark0, mut lit0) = visibility_margins(satrec, observer, twilight, t0)?;
+    for i in 1..=n {
+        let t1 = pass.aos + (pass.los - pass.aos) * (i as f64) / (n as f64);
+        let (dark1, lit1) = visibility_margins(satrec, observer, twilight, t1)?;
+        if (dark0 < 0.0) != (dark1 < 0.0) {
+            breaks.push(find_root(
+                |t| visibility_margins(satrec, observer, twilight, t).map(|m| m.0),
+                t0,
+                t1,
+                TIME_TOLERANCE,
+            )?);
+        }
+        if (lit0 < 0.0) != (lit1 < 0.0) {
+            breaks.push(find_root(
+                |t| visibility_margins(satrec, observer, twilight, t).map(|m| m.1),
+                t0,
+                t1,
+                TIME_TOLERANCE,
+            )?);
+        }
+        t0 = t1;
+        dark0 = dark1;
+        lit0 = lit1;
+    }
+    breaks.push(pass.los);
+    breaks.sort_by(|a, b| a.total_cmp(b));
+
+    let mut segments: Vec<VisibleSegment> = Vec::new();
+    for window in breaks.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        if end - start < TIME_TOLERANCE {
+            continue;
+        }
+        let (dark, lit) = visibility_margins(satrec, observer, twilight, 0.5 * (start + end))?;
+        if dark < 0.0 || lit < 0.0 {
+            continue;
+        }
+        match segments.last_mut() {
+            Some(last) if start - last.end < TIME_TOLERANCE => last.end = end,
+            _ => segments.push(VisibleSegment {
+                start,
+                end,
+                max_elevation: 0.0,
+            }),
+        }
+    }
+
+    // the elevation rises to the culmination and falls after it, so within
+    // each segment it peaks at the point closest to it
+    for segment in segments.iter_mut() {
+        let peak = pass.tca.clamp(segment.start, segment.end);
+        segment.max_elevation = look_angles_at(satrec, observer, peak)?.elevation();
+    }
+
+    Ok(segments)
+}
+
+/// Find the passes of `satrec` above `min_elevation` (radians) between Julian
+/// dates `start` and `end` during which the satellite is sunlit while the sky
+/// at the observer is at least as dark as `twilight`.
+///
+/// Each returned pass lists the sub-segments in which both conditions hold;
+/// a satellite in penumbra counts as sunlit.
+pub fn find_visible_passes(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    start: f64,
+    end: f64,
+    min_elevation: f64,
+    twilight: Twilight,
+) -> Result<Vec<VisiblePass>, PropagatorError> {
+    let mut visible_passes = Vec::new();
+    for pass in find_passes(satrec, observer, start, end, min_elevation)? {
+        let segments = visible_segments(satrec, observer, twilight, &pass)?;
+        if !segments.is_empty() {
+            visible_passes.push(VisiblePass { pass, segments });
+        }
+    }
+    Ok(visible_passes)
+}
+
+#[wasm_bindgen(js_name = "findVisiblePasses")]
+pub fn js_find_visible_passes(
+    satrec: &mut SatRec,
+    observer: &GeodeticLocation,
+    start: f64,
+    end: f64,
+    min_elevation: f64,
+    twilight: Twilight,
+) -> Result<JsValue, JsValue> {
+    match find_visible_passes(satrec, observer, start, end, min_elevation, twilight) {
+        Ok(passes) => Ok(to_value(&passes).unwrap()),
+        Err(error) => Err(to_value(&error).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eclipse::{eclipse, EclipseState};
+    use crate::ext::jday;
+    use crate::io::twoline2satrec;
+
+    #[test]
+    fn test_visible_passes_iss() {
+        let mut satrec = twoline2satrec(
+            "1 25544U 98067A   24001.50000000  .00016717  00000-0  30164-3 0  9993",
+            "2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.50377579432032",
+        );
+        let observer = GeodeticLocation::from_degrees(-0.1276, 51.5072, 0.0);
+        let start = jday(2024.0, 1.0, 1.0, 12.0, 0.0, 0.0, 0.0);
+        let end = start + 3.0;
+        let min_elevation = 10.0 * DEG2RAD;
+
+        let all = find_passes(&mut satrec, &observer, start, end, min_elevation).unwrap();
+        let visible = find_visible_passes(
+            &mut satrec,
+            &observer,
+            start,
+            end,
+            min_elevation,
+            Twilight::Civil,
+        )
+        .unwrap();
+
+        assert!(!visible.is_empty() && visible.len() < all.len());
+        for visible_pass in &visible {
+            for segment in &visible_pass.segments {
+                assert!(segment.start >= visible_pass.pass.aos);
+                assert!(segment.end <= visible_pass.pass.los);
+                assert!(segment.max_elevation <= visible_pass.pass.max_elevation + 1e-9);
+
+                let mid = 0.5 * (segment.start + segment.end);
+                let sun = ecf_to_look_angles(&observer, &sun_position_ecf(mid));
+                assert!(sun.elevation() < Twilight::Civil.sun_elevation());
+                let tsince = (mid - satrec.jdsatepoch) * MINUTES_PER_DAY;
+                let result = sgp4(&mut satrec, tsince);
+                let state = eclipse(result.unwrap().position(), mid).state;
+                assert_ne!(state, EclipseState::Umbra);
+            }
+        }
+    }
+}